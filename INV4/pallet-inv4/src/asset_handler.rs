@@ -0,0 +1,354 @@
+use super::pallet::*;
+use frame_support::pallet_prelude::*;
+#[cfg(feature = "uniques")]
+use frame_support::traits::{Contains, Nothing};
+#[cfg(feature = "rmrk")]
+use rmrk_traits::{
+    primitives::{CollectionId, NftId},
+    AccountIdOrCollectionNftTuple, Collection, Nft,
+};
+use sp_std::marker::PhantomData;
+
+/// Trait for checking ownership of and moving one kind of asset an IP Set can hold.
+///
+/// Supplied per asset kind through `Config`, `()` rejects every asset of that kind.
+pub trait IpsAssetHandler<T: Config, Asset> {
+    /// Ensure `owner` owns `asset` and that it can be placed in an IP Set
+    fn ensure_owner(asset: &Asset, owner: &T::AccountId) -> DispatchResult;
+
+    /// Move `asset` from `from` to the IP Set account `ips_account`
+    fn transfer_to_ips(
+        asset: &Asset,
+        from: &T::AccountId,
+        ips_account: &T::AccountId,
+    ) -> DispatchResult;
+
+    /// Move `asset` from the IP Set account `ips_account` to `new_owner`
+    fn transfer_from_ips(
+        asset: &Asset,
+        ips_account: &T::AccountId,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult;
+}
+
+/// Asset kind not supported by the runtime
+impl<T: Config, Asset> IpsAssetHandler<T, Asset> for () {
+    fn ensure_owner(_asset: &Asset, _owner: &T::AccountId) -> DispatchResult {
+        Err(Error::<T>::UnsupportedAsset.into())
+    }
+
+    fn transfer_to_ips(
+        _asset: &Asset,
+        _from: &T::AccountId,
+        _ips_account: &T::AccountId,
+    ) -> DispatchResult {
+        Err(Error::<T>::UnsupportedAsset.into())
+    }
+
+    fn transfer_from_ips(
+        _asset: &Asset,
+        _ips_account: &T::AccountId,
+        _new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        Err(Error::<T>::UnsupportedAsset.into())
+    }
+}
+
+/// IP Files from `pallet-ipf`
+pub struct IpfHandler<T>(PhantomData<T>);
+
+impl<T: Config> IpsAssetHandler<T, <T as ipf::Config>::IpfId> for IpfHandler<T> {
+    fn ensure_owner(ipf_id: &<T as ipf::Config>::IpfId, owner: &T::AccountId) -> DispatchResult {
+        ensure!(
+            ipf::IpfStorage::<T>::get(ipf_id)
+                .ok_or(Error::<T>::IpfNotFound)?
+                .owner
+                == *owner,
+            Error::<T>::NoPermission
+        );
+
+        Ok(())
+    }
+
+    fn transfer_to_ips(
+        ipf_id: &<T as ipf::Config>::IpfId,
+        from: &T::AccountId,
+        ips_account: &T::AccountId,
+    ) -> DispatchResult {
+        ipf::Pallet::<T>::send(from.clone(), *ipf_id, ips_account.clone())
+    }
+
+    fn transfer_from_ips(
+        ipf_id: &<T as ipf::Config>::IpfId,
+        ips_account: &T::AccountId,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        ipf::Pallet::<T>::send(ips_account.clone(), *ipf_id, new_owner.clone())
+    }
+}
+
+/// RMRK NFTs from `pallet-rmrk-core`
+#[cfg(feature = "rmrk")]
+pub struct RmrkNftHandler<T>(PhantomData<T>);
+
+#[cfg(feature = "rmrk")]
+impl<T: Config + pallet_rmrk_core::Config> IpsAssetHandler<T, (CollectionId, NftId)>
+    for RmrkNftHandler<T>
+{
+    fn ensure_owner(
+        (collection_id, nft_id): &(CollectionId, NftId),
+        owner: &T::AccountId,
+    ) -> DispatchResult {
+        let rmrk_nft = pallet_rmrk_core::Nfts::<T>::get(collection_id, nft_id)
+            .ok_or(Error::<T>::IpfNotFound)?;
+
+        ensure!(
+            rmrk_nft.owner == AccountIdOrCollectionNftTuple::AccountId(owner.clone()),
+            Error::<T>::NoPermission
+        );
+
+        ensure!(rmrk_nft.transferable, Error::<T>::NoPermission);
+
+        Ok(())
+    }
+
+    fn transfer_to_ips(
+        (collection_id, nft_id): &(CollectionId, NftId),
+        from: &T::AccountId,
+        ips_account: &T::AccountId,
+    ) -> DispatchResult {
+        pallet_rmrk_core::Pallet::<T>::nft_send(
+            from.clone(),
+            *collection_id,
+            *nft_id,
+            AccountIdOrCollectionNftTuple::AccountId(ips_account.clone()),
+        )?;
+
+        Ok(())
+    }
+
+    fn transfer_from_ips(
+        (collection_id, nft_id): &(CollectionId, NftId),
+        ips_account: &T::AccountId,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        pallet_rmrk_core::Pallet::<T>::nft_send(
+            ips_account.clone(),
+            *collection_id,
+            *nft_id,
+            AccountIdOrCollectionNftTuple::AccountId(new_owner.clone()),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// RMRK collections from `pallet-rmrk-core`, owned through the collection issuer
+#[cfg(feature = "rmrk")]
+pub struct RmrkCollectionHandler<T>(PhantomData<T>);
+
+#[cfg(feature = "rmrk")]
+impl<T: Config + pallet_rmrk_core::Config> IpsAssetHandler<T, CollectionId>
+    for RmrkCollectionHandler<T>
+{
+    fn ensure_owner(collection_id: &CollectionId, owner: &T::AccountId) -> DispatchResult {
+        ensure!(
+            pallet_rmrk_core::Collections::<T>::get(collection_id)
+                .ok_or(Error::<T>::IpfNotFound)?
+                .issuer
+                == *owner,
+            Error::<T>::NoPermission
+        );
+
+        Ok(())
+    }
+
+    fn transfer_to_ips(
+        collection_id: &CollectionId,
+        _from: &T::AccountId,
+        ips_account: &T::AccountId,
+    ) -> DispatchResult {
+        pallet_rmrk_core::Pallet::<T>::collection_change_issuer(
+            *collection_id,
+            ips_account.clone(),
+        )?;
+
+        Ok(())
+    }
+
+    fn transfer_from_ips(
+        collection_id: &CollectionId,
+        _ips_account: &T::AccountId,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        pallet_rmrk_core::Pallet::<T>::collection_change_issuer(*collection_id, new_owner.clone())?;
+
+        Ok(())
+    }
+}
+
+/// RMRK collections are also `pallet_uniques` collections, for use as the `Excluded` filter of `UniquesHandler`
+#[cfg(feature = "rmrk")]
+pub struct RmrkCollections<T>(PhantomData<T>);
+
+#[cfg(feature = "rmrk")]
+impl<T: pallet_rmrk_core::Config> Contains<CollectionId> for RmrkCollections<T> {
+    fn contains(collection_id: &CollectionId) -> bool {
+        pallet_rmrk_core::Collections::<T>::contains_key(collection_id)
+    }
+}
+
+/// Item of a `pallet_uniques` collection: (collection ID, item ID)
+#[cfg(feature = "uniques")]
+pub type UniquesItemOf<T> = (
+    <T as pallet_uniques::Config>::CollectionId,
+    <T as pallet_uniques::Config>::ItemId,
+);
+
+/// Items from `pallet-uniques`. Items of collections in `Excluded` are rejected,
+/// so collections managed by other pallets (like RMRK) can't get out of sync.
+#[cfg(feature = "uniques")]
+pub struct UniquesHandler<T, Excluded = Nothing>(PhantomData<(T, Excluded)>);
+
+#[cfg(feature = "uniques")]
+impl<T, Excluded> IpsAssetHandler<T, UniquesItemOf<T>> for UniquesHandler<T, Excluded>
+where
+    T: Config + pallet_uniques::Config,
+    Excluded: Contains<<T as pallet_uniques::Config>::CollectionId>,
+{
+    fn ensure_owner(
+        (collection_id, item_id): &UniquesItemOf<T>,
+        owner: &T::AccountId,
+    ) -> DispatchResult {
+        ensure!(!Excluded::contains(collection_id), Error::<T>::NoPermission);
+
+        ensure!(
+            pallet_uniques::Pallet::<T>::owner(*collection_id, *item_id)
+                .ok_or(Error::<T>::IpfNotFound)?
+                == *owner,
+            Error::<T>::NoPermission
+        );
+
+        Ok(())
+    }
+
+    fn transfer_to_ips(
+        item: &UniquesItemOf<T>,
+        from: &T::AccountId,
+        ips_account: &T::AccountId,
+    ) -> DispatchResult {
+        // `do_transfer` moves the item from whoever owns it
+        Self::ensure_owner(item, from)?;
+
+        pallet_uniques::Pallet::<T>::do_transfer(item.0, item.1, ips_account.clone(), |_, _| Ok(()))
+    }
+
+    fn transfer_from_ips(
+        item: &UniquesItemOf<T>,
+        ips_account: &T::AccountId,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        Self::ensure_owner(item, ips_account)?;
+
+        pallet_uniques::Pallet::<T>::do_transfer(item.0, item.1, new_owner.clone(), |_, _| Ok(()))
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Ensure `owner` owns `asset` through the handler of its kind
+    pub(crate) fn ensure_asset_owner(asset: &AnyIdOf<T>, owner: &T::AccountId) -> DispatchResult {
+        match asset {
+            // Nested IPS needs rewrite
+            AnyId::IpsId(_) => Ok(()),
+            AnyId::IpfId(ipf_id) => T::IpfAssets::ensure_owner(ipf_id, owner),
+            #[cfg(feature = "rmrk")]
+            AnyId::RmrkNft(nft) => T::RmrkNftAssets::ensure_owner(nft, owner),
+            #[cfg(feature = "rmrk")]
+            AnyId::RmrkCollection(collection_id) => {
+                T::RmrkCollectionAssets::ensure_owner(collection_id, owner)
+            }
+            #[cfg(feature = "uniques")]
+            AnyId::UniquesItem(collection_id, item_id) => {
+                T::UniquesAssets::ensure_owner(&(*collection_id, *item_id), owner)
+            }
+            // Amounts are checked when moved
+            #[cfg(feature = "assets")]
+            AnyId::FungibleAsset(..) => Ok(()),
+        }
+    }
+
+    /// Whether `asset` is listed in `IpsAssets`, fungible assets are tracked in `FungibleHoldings` instead
+    #[cfg(feature = "assets")]
+    pub(crate) fn is_listed_asset(asset: &AnyIdOf<T>) -> bool {
+        !matches!(asset, AnyId::FungibleAsset(..))
+    }
+
+    /// Every asset is listed in `IpsAssets` without the `assets` feature
+    #[cfg(not(feature = "assets"))]
+    pub(crate) fn is_listed_asset(_asset: &AnyIdOf<T>) -> bool {
+        true
+    }
+
+    /// Move `asset` into `ips_account`. With no `from`, the asset is already owned by `ips_account`.
+    #[cfg_attr(not(feature = "assets"), allow(unused_variables))]
+    pub(crate) fn transfer_asset_to_ips(
+        ips_id: T::IpId,
+        asset: &AnyIdOf<T>,
+        from: Option<&T::AccountId>,
+        ips_account: &T::AccountId,
+    ) -> DispatchResult {
+        match (asset, from) {
+            #[cfg(feature = "assets")]
+            (AnyId::FungibleAsset(asset_id, amount), from) => {
+                Pallet::<T>::deposit_fungible(ips_id, ips_account, from, *asset_id, *amount)
+            }
+            (_, None) | (AnyId::IpsId(_), _) => Ok(()),
+            (AnyId::IpfId(ipf_id), Some(from)) => {
+                T::IpfAssets::transfer_to_ips(ipf_id, from, ips_account)
+            }
+            #[cfg(feature = "rmrk")]
+            (AnyId::RmrkNft(nft), Some(from)) => {
+                T::RmrkNftAssets::transfer_to_ips(nft, from, ips_account)
+            }
+            #[cfg(feature = "rmrk")]
+            (AnyId::RmrkCollection(collection_id), Some(from)) => {
+                T::RmrkCollectionAssets::transfer_to_ips(collection_id, from, ips_account)
+            }
+            #[cfg(feature = "uniques")]
+            (AnyId::UniquesItem(collection_id, item_id), Some(from)) => {
+                T::UniquesAssets::transfer_to_ips(&(*collection_id, *item_id), from, ips_account)
+            }
+        }
+    }
+
+    /// Move `asset` out of `ips_account` to `new_owner`
+    #[cfg_attr(not(feature = "assets"), allow(unused_variables))]
+    pub(crate) fn transfer_asset_from_ips(
+        ips_id: T::IpId,
+        asset: &AnyIdOf<T>,
+        ips_account: &T::AccountId,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        match asset {
+            // Nested IPS needs rewrite
+            AnyId::IpsId(_) => Ok(()),
+            AnyId::IpfId(ipf_id) => T::IpfAssets::transfer_from_ips(ipf_id, ips_account, new_owner),
+            #[cfg(feature = "rmrk")]
+            AnyId::RmrkNft(nft) => T::RmrkNftAssets::transfer_from_ips(nft, ips_account, new_owner),
+            #[cfg(feature = "rmrk")]
+            AnyId::RmrkCollection(collection_id) => {
+                T::RmrkCollectionAssets::transfer_from_ips(collection_id, ips_account, new_owner)
+            }
+            #[cfg(feature = "uniques")]
+            AnyId::UniquesItem(collection_id, item_id) => T::UniquesAssets::transfer_from_ips(
+                &(*collection_id, *item_id),
+                ips_account,
+                new_owner,
+            ),
+            #[cfg(feature = "assets")]
+            AnyId::FungibleAsset(asset_id, amount) => {
+                Pallet::<T>::withdraw_fungible(ips_id, ips_account, *asset_id, *amount, new_owner)
+            }
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use super::pallet::{self, *};
+use crate::util::derive_ips_account;
+use frame_support::{
+    pallet_prelude::*,
+    traits::{
+        BalanceStatus, Currency, ExistenceRequirement, NamedReservableCurrency, WithdrawReasons,
+    },
+};
+use sp_arithmetic::traits::{Saturating, Zero};
+use sp_runtime::Perbill;
+use sp_std::marker::PhantomData;
+
+/// Identifier of the multisig call fees reserved from voters
+pub const MULTISIG_FEE_RESERVE_ID: [u8; 8] = *b"inv4/fee";
+
+/// Trait for deciding how the fee of a multisig call is split between its voters.
+///
+/// Voter fees are reserved while the vote is ongoing, unreserved if the vote is withdrawn or the call fails
+/// and paid into the IP Set account once the call executed successfully.
+pub trait MultisigFeeHandler<T: Config> {
+    /// Fee paid by a voter whose `votes` are not enough to execute the call.
+    fn vote_fee(
+        fee: <T as pallet::Config>::Balance,
+        votes: <T as pallet::Config>::Balance,
+        votes_required: <T as pallet::Config>::Balance,
+        is_proposer: bool,
+    ) -> <T as pallet::Config>::Balance;
+
+    /// Fee paid by the voter whose votes execute the call, `already_paid` being the sum paid by previous voters.
+    fn execution_fee(
+        fee: <T as pallet::Config>::Balance,
+        already_paid: <T as pallet::Config>::Balance,
+    ) -> <T as pallet::Config>::Balance {
+        fee.saturating_sub(already_paid)
+    }
+
+    /// Part of the fee taken from the IP Set account itself once the call executed successfully.
+    /// It is burned, like a transaction fee.
+    fn ips_fee(_fee: <T as pallet::Config>::Balance) -> <T as pallet::Config>::Balance {
+        Zero::zero()
+    }
+}
+
+/// The IP Set account covers the fee of executed calls, voters pay nothing.
+pub struct IpsPaysFee<T>(PhantomData<T>);
+
+impl<T: Config> MultisigFeeHandler<T> for IpsPaysFee<T> {
+    fn vote_fee(
+        _fee: <T as pallet::Config>::Balance,
+        _votes: <T as pallet::Config>::Balance,
+        _votes_required: <T as pallet::Config>::Balance,
+        _is_proposer: bool,
+    ) -> <T as pallet::Config>::Balance {
+        Zero::zero()
+    }
+
+    fn execution_fee(
+        _fee: <T as pallet::Config>::Balance,
+        _already_paid: <T as pallet::Config>::Balance,
+    ) -> <T as pallet::Config>::Balance {
+        Zero::zero()
+    }
+
+    fn ips_fee(fee: <T as pallet::Config>::Balance) -> <T as pallet::Config>::Balance {
+        fee
+    }
+}
+
+/// The account proposing the call pays the full fee upfront.
+pub struct ProposerPaysFee<T>(PhantomData<T>);
+
+impl<T: Config> MultisigFeeHandler<T> for ProposerPaysFee<T> {
+    fn vote_fee(
+        fee: <T as pallet::Config>::Balance,
+        _votes: <T as pallet::Config>::Balance,
+        _votes_required: <T as pallet::Config>::Balance,
+        is_proposer: bool,
+    ) -> <T as pallet::Config>::Balance {
+        if is_proposer {
+            fee
+        } else {
+            Zero::zero()
+        }
+    }
+}
+
+/// Every voter pays the share of the fee matching their share of the votes required,
+/// the voter executing the call pays whatever is left.
+pub struct ProportionalFee<T>(PhantomData<T>);
+
+impl<T: Config> MultisigFeeHandler<T> for ProportionalFee<T> {
+    fn vote_fee(
+        fee: <T as pallet::Config>::Balance,
+        votes: <T as pallet::Config>::Balance,
+        votes_required: <T as pallet::Config>::Balance,
+        _is_proposer: bool,
+    ) -> <T as pallet::Config>::Balance {
+        Perbill::from_rational(votes, votes_required) * fee
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Reserve `amount` from `payer` as (part of) a multisig call fee
+    pub(crate) fn reserve_multisig_fee(
+        payer: &T::AccountId,
+        amount: <T as pallet::Config>::Balance,
+    ) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        <T as pallet::Config>::Currency::reserve_named(&MULTISIG_FEE_RESERVE_ID, payer, amount)
+    }
+
+    /// Return `amount` of a multisig call fee reserved from `payer`
+    pub(crate) fn refund_multisig_fee(
+        payer: &T::AccountId,
+        amount: <T as pallet::Config>::Balance,
+    ) {
+        if amount.is_zero() {
+            return;
+        }
+
+        <T as pallet::Config>::Currency::unreserve_named(&MULTISIG_FEE_RESERVE_ID, payer, amount);
+    }
+
+    /// Move `amount` of a multisig call fee reserved from `payer` to the IP Set account
+    pub(crate) fn collect_multisig_fee(
+        ips_id: T::IpId,
+        payer: &T::AccountId,
+        amount: <T as pallet::Config>::Balance,
+    ) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        let not_collected = <T as pallet::Config>::Currency::repatriate_reserved_named(
+            &MULTISIG_FEE_RESERVE_ID,
+            payer,
+            &derive_ips_account::<T>(ips_id, None),
+            amount,
+            BalanceStatus::Free,
+        )?;
+
+        ensure!(not_collected.is_zero(), Error::<T>::MultisigFeeNotReserved);
+
+        Ok(())
+    }
+
+    /// Burn `amount` from the IP Set account as its share of the fee of an executed multisig call
+    pub(crate) fn charge_ips_fee(
+        ips_id: T::IpId,
+        amount: <T as pallet::Config>::Balance,
+    ) -> DispatchResult {
+        if amount.is_zero() {
+            return Ok(());
+        }
+
+        <T as pallet::Config>::Currency::withdraw(
+            &derive_ips_account::<T>(ips_id, None),
+            amount,
+            WithdrawReasons::FEE,
+            ExistenceRequirement::KeepAlive,
+        )?;
+
+        Ok(())
+    }
+}
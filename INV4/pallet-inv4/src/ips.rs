@@ -1,10 +1,17 @@
 use super::pallet::*;
 use crate::{ipl::LicenseList, util::derive_ips_account};
-use frame_support::pallet_prelude::*;
+#[cfg(feature = "assets")]
+use frame_support::traits::tokens::fungibles::Transfer;
+use frame_support::{
+    dispatch::RawOrigin,
+    pallet_prelude::*,
+    traits::{Currency, ExistenceRequirement, LockIdentifier},
+};
 use frame_system::{ensure_signed, pallet_prelude::*};
 use primitives::{IpInfo, IpsType, OneOrPercent, Parentage};
-use rmrk_traits::{Collection, Nft};
 use sp_arithmetic::traits::{CheckedAdd, One, Zero};
+#[cfg(feature = "assets")]
+use sp_arithmetic::traits::{CheckedSub, Saturating};
 use sp_runtime::traits::StaticLookup;
 use sp_std::{convert::TryInto, vec::Vec};
 
@@ -12,9 +19,46 @@ pub type IpsIndexOf<T> = <T as Config>::IpId;
 
 pub type IpsMetadataOf<T> = BoundedVec<u8, <T as Config>::MaxMetadata>;
 
+/// Lock held on IPFs listed in an IP Set
+pub const IPS_LOCK_ID: LockIdentifier = *b"inv4/ips";
+
+/// Settings of an IP Set created along with its IPFs by `pallet-ipf`'s `mint_batch`,
+/// the same as `create_ips` takes
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct NewIpsParams<Licenses> {
+    pub metadata: Vec<u8>,
+    pub allow_replica: bool,
+    pub license: Licenses,
+    pub execution_threshold: OneOrPercent,
+    pub default_asset_weight: OneOrPercent,
+    pub default_permission: bool,
+}
+
+impl<T: Config> ipf::IpsCreator<T::AccountId, <T as ipf::Config>::IpfId> for Pallet<T> {
+    type Params = NewIpsParams<<T as Config>::Licenses>;
+
+    fn create_ips(
+        owner: T::AccountId,
+        ipfs: Vec<<T as ipf::Config>::IpfId>,
+        params: Self::Params,
+    ) -> DispatchResult {
+        Pallet::<T>::inner_create_ips(
+            RawOrigin::Signed(owner).into(),
+            params.metadata,
+            ipfs.into_iter().map(AnyId::IpfId).collect(),
+            params.allow_replica,
+            params.license,
+            params.execution_threshold,
+            params.default_asset_weight,
+            params.default_permission,
+        )
+        .map(|_| ())
+    }
+}
+
 impl<T: Config> Pallet<T> {
-    /// Create IP Set
-    pub(crate) fn inner_create_ips(
+    /// Create IP Set, returning its ID
+    pub fn inner_create_ips(
         owner: OriginFor<T>,
         metadata: Vec<u8>,
         assets: Vec<AnyIdOf<T>>,
@@ -23,7 +67,7 @@ impl<T: Config> Pallet<T> {
         ipl_execution_threshold: OneOrPercent,
         ipl_default_asset_weight: OneOrPercent,
         ipl_default_permission: bool,
-    ) -> DispatchResult {
+    ) -> Result<T::IpId, DispatchError> {
         // IPS inside IPS disabled for now. Needs rewrite.
         ensure!(
             !assets
@@ -33,7 +77,7 @@ impl<T: Config> Pallet<T> {
             Error::<T>::IpsInsideIpsDisabled
         );
 
-        NextIpId::<T>::try_mutate(|ips_id| -> DispatchResult {
+        NextIpId::<T>::try_mutate(|ips_id| -> Result<T::IpId, DispatchError> {
             let creator = ensure_signed(owner.clone())?;
 
             let bounded_metadata: BoundedVec<u8, T::MaxMetadata> = metadata
@@ -48,73 +92,14 @@ impl<T: Config> Pallet<T> {
 
             // Verify `creator` has permission to add each item in `assets` to new IP Set
             for asset in assets.clone() {
-                match asset {
-                    AnyId::IpsId(_) => (),
-                    AnyId::IpfId(ipf_id) => {
-                        ensure!(
-                            ipf::IpfStorage::<T>::get(ipf_id)
-                                .ok_or(Error::<T>::IpfNotFound)?
-                                .owner
-                                == creator,
-                            Error::<T>::NoPermission
-                        );
-                    }
-                    AnyId::RmrkNft((collection_id, nft_id)) => {
-                        let rmrk_nft = pallet_rmrk_core::Nfts::<T>::get(collection_id, nft_id)
-                            .ok_or(Error::<T>::IpfNotFound)?;
-
-                        ensure!(
-                            rmrk_nft.owner
-                                == rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(
-                                    creator.clone()
-                                ),
-                            Error::<T>::NoPermission
-                        );
-
-                        ensure!(rmrk_nft.transferable, Error::<T>::NoPermission);
-                    }
-                    AnyId::RmrkCollection(collection_id) => {
-                        ensure!(
-                            pallet_rmrk_core::Collections::<T>::get(collection_id)
-                                .ok_or(Error::<T>::IpfNotFound)?
-                                .issuer
-                                == creator.clone(),
-                            Error::<T>::NoPermission
-                        );
-                    }
-                }
+                Pallet::<T>::ensure_asset_owner(&asset, &creator)?;
             }
 
             // Generate new `AccountId` to represent new IP Set being created
             let ips_account = derive_ips_account::<T>(current_id, None);
 
-            // Transfer ownership (issuer for `RmrkCollection`) to `ips_account` for each item in `assets`
-            for asset in assets.clone() {
-                match asset {
-                    AnyId::IpsId(_) => (),
-                    AnyId::IpfId(ipf_id) => {
-                        ipf::Pallet::<T>::send(creator.clone(), ipf_id, ips_account.clone())?
-                    }
-                    AnyId::RmrkNft((collection_id, nft_id)) => {
-                        pallet_rmrk_core::Pallet::<T>::nft_send(
-                            creator.clone(),
-                            collection_id,
-                            nft_id,
-                            rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(
-                                ips_account.clone(),
-                            ),
-                        )?;
-                    }
-                    AnyId::RmrkCollection(collection_id) => {
-                        pallet_rmrk_core::Pallet::<T>::collection_change_issuer(
-                            collection_id,
-                            ips_account.clone(),
-                        )?;
-                    }
-                }
-            }
-
             // `ips_account` needs the existential deposit, so we send that
+            // This happens first since fungible assets can only be sent to an existing account
             pallet_balances::Pallet::<T>::transfer_keep_alive(
                 owner.clone(),
                 T::Lookup::unlookup(ips_account.clone()),
@@ -122,6 +107,18 @@ impl<T: Config> Pallet<T> {
             )
             .map_err(|error_with_post_info| error_with_post_info.error)?;
 
+            // Transfer ownership (issuer for `RmrkCollection`) to `ips_account` for each item in `assets`
+            for asset in assets.clone() {
+                Pallet::<T>::transfer_asset_to_ips(
+                    current_id,
+                    &asset,
+                    Some(&creator),
+                    &ips_account,
+                )?;
+
+                Pallet::<T>::list_asset(current_id, &asset)?;
+            }
+
             // Send IP Set `creator` 1,000,000 "IPT0" tokens
             // Token has 6 decimal places: 1,000,000 / 10^6 = 1 IPTO token
             // This allows for token divisiblity
@@ -134,10 +131,6 @@ impl<T: Config> Pallet<T> {
             let info = IpInfo {
                 parentage: Parentage::Parent(ips_account.clone()),
                 metadata: bounded_metadata,
-                data: assets
-                    .clone()
-                    .try_into()
-                    .map_err(|_| Error::<T>::MaxMetadataExceeded)?,
                 ips_type: IpsType::Normal,
                 allow_replica,
 
@@ -159,7 +152,7 @@ impl<T: Config> Pallet<T> {
                 assets,
             });
 
-            Ok(())
+            Ok(current_id)
         })
     }
 
@@ -185,8 +178,6 @@ impl<T: Config> Pallet<T> {
 
             let info = ips_info.take().ok_or(Error::<T>::IpsNotFound)?;
 
-            let parent_id = ips_id;
-
             // Get highest level IPS `AccountId` in the hierarchy
             let ips_account = match info.parentage.clone() {
                 Parentage::Parent(ips_account) => ips_account,
@@ -203,119 +194,29 @@ impl<T: Config> Pallet<T> {
                 Error::<T>::NoPermission
             );
 
-            // Verify valid permission to add each item in `assets` to IP Set
+            // Verify valid permission to add each item in `assets` to IP Set, then send it to `ips_account`
             for asset in assets.clone() {
-                match asset {
-                    AnyId::IpsId(_) => (),
-                    // {
-                    //     if let Parentage::Parent(acc) = IpStorage::<T>::get(ips_id)
-                    //         .ok_or(Error::<T>::IpsNotFound)?
-                    //         .parentage
-                    //     {
-                    //         ensure!(
-                    //             caller_account
-                    //                 == multi_account_id::<T, T::IpId>(parent_id, Some(acc)),
-                    //             Error::<T>::NoPermission
-                    //         );
-                    //     } else {
-                    //         return Err(Error::<T>::NotParent.into());
-                    //     }
-                    // }
-                    AnyId::IpfId(ipf_id) => {
-                        let this_ipf_owner = ipf::IpfStorage::<T>::get(ipf_id)
-                            .ok_or(Error::<T>::IpfNotFound)?
-                            .owner;
-
-                        // Ensure: either it's the IP Set itself or it's the IP Set with the include_caller option from multisig.
-                        // We need that second one so we can allow someone to start a multisig call to include assets
-                        // that they own without manually sending to the IPS and then starting a multisig
-                        ensure!(
-                            this_ipf_owner.clone() == ips_account
-                                || caller_account
-                                    == derive_ips_account::<T>(parent_id, Some(&this_ipf_owner)),
-                            Error::<T>::NoPermission
-                        );
-                    }
-                    AnyId::RmrkNft((collection_id, nft_id)) => {
-                        let this_rmrk_nft = pallet_rmrk_core::Nfts::<T>::get(collection_id, nft_id)
-                            .ok_or(Error::<T>::IpfNotFound)?;
-                        let this_rmrk_owner = this_rmrk_nft.owner;
-
-                        // Ensure IP Set is already owner of the NFT or owned by account initiating multisig call with `include_caller` option
-                        ensure!(
-                            this_rmrk_owner.clone()
-                                == rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(
-                                    ips_account.clone()
-                                )
-                                || if let rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(
-                                    rmrk_owner_account,
-                                ) = this_rmrk_owner.clone()
-                                {
-                                    caller_account
-                                        == derive_ips_account::<T>(
-                                            parent_id,
-                                            Some(&rmrk_owner_account),
-                                        )
-                                } else {
-                                    false
-                                },
-                            Error::<T>::NoPermission
-                        );
-
-                        ensure!(this_rmrk_nft.transferable, Error::<T>::NoPermission);
-                    }
-                    AnyId::RmrkCollection(collection_id) => {
-                        let this_rmrk_issuer =
-                            pallet_rmrk_core::Collections::<T>::get(collection_id)
-                                .ok_or(Error::<T>::IpfNotFound)?
-                                .issuer;
-                        // Ensure IP Set is already owner(issuer) of NFT collection or
-                        // initater of multisig call with `include_caller` is the owner(issuer)
-                        ensure!(
-                            this_rmrk_issuer.clone() == ips_account.clone()
-                                || caller_account
-                                    == derive_ips_account::<T>(parent_id, Some(&this_rmrk_issuer),),
-                            Error::<T>::NoPermission
-                        );
-                    }
-                }
-            }
+                // Ensure: either it's the IP Set itself or it's the IP Set with the include_caller option from multisig.
+                // We need that second one so we can allow someone to start a multisig call to include assets
+                // that they own without manually sending to the IPS and then starting a multisig.
+                // Fungible assets are moved out of `original_caller`s balance (or already held by `ips_account`),
+                // which `caller_account` was already verified to be derived from
+                let source = if !Pallet::<T>::is_listed_asset(&asset) {
+                    original_caller.as_ref()
+                } else if Pallet::<T>::ensure_asset_owner(&asset, &ips_account).is_ok() {
+                    None
+                } else {
+                    let original_caller =
+                        original_caller.as_ref().ok_or(Error::<T>::NoPermission)?;
 
-            // Permissions have been verified, now send all assets to `ips_account`
-            for asset in assets.clone() {
-                match asset {
-                    AnyId::IpsId(_) => (),
-                    AnyId::IpfId(ipf_id) => ipf::Pallet::<T>::send(
-                        ipf::IpfStorage::<T>::get(ipf_id)
-                            .ok_or(Error::<T>::IpfNotFound)?
-                            .owner,
-                        ipf_id,
-                        ips_account.clone(),
-                    )?,
-                    AnyId::RmrkNft((collection_id, nft_id)) => {
-                        if let rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(
-                            rmrk_owner_account,
-                        ) = pallet_rmrk_core::Nfts::<T>::get(collection_id, nft_id)
-                            .ok_or(Error::<T>::IpfNotFound)?
-                            .owner
-                        {
-                            pallet_rmrk_core::Pallet::<T>::nft_send(
-                                rmrk_owner_account,
-                                collection_id,
-                                nft_id,
-                                rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(
-                                    ips_account.clone(),
-                                ),
-                            )?;
-                        }
-                    }
-                    AnyId::RmrkCollection(collection_id) => {
-                        pallet_rmrk_core::Pallet::<T>::collection_change_issuer(
-                            collection_id,
-                            ips_account.clone(),
-                        )?;
-                    }
-                }
+                    Pallet::<T>::ensure_asset_owner(&asset, original_caller)?;
+
+                    Some(original_caller)
+                };
+
+                Pallet::<T>::transfer_asset_to_ips(ips_id, &asset, source, &ips_account)?;
+
+                Pallet::<T>::list_asset(ips_id, &asset)?;
             }
 
             // for any_id in assets.clone().into_iter() {
@@ -352,7 +253,7 @@ impl<T: Config> Pallet<T> {
             //     }
             // }
 
-            // Update IpInfo struct in storage to hold either new assets, new metadata, or both
+            // Update IpInfo struct in storage to hold new metadata
             *ips_info = Some(IpInfo {
                 parentage: info.parentage,
                 metadata: if let Some(metadata) = new_metadata.clone() {
@@ -362,13 +263,6 @@ impl<T: Config> Pallet<T> {
                 } else {
                     info.metadata.clone()
                 },
-                data: info
-                    .data
-                    .into_iter()
-                    .chain(assets.clone().into_iter())
-                    .collect::<Vec<AnyIdOf<T>>>()
-                    .try_into()
-                    .map_err(|_| Error::<T>::MaxMetadataExceeded)?,
                 ips_type: info.ips_type,
                 allow_replica: info.allow_replica,
 
@@ -391,13 +285,15 @@ impl<T: Config> Pallet<T> {
         })
     }
 
-    /// Remove an asset/assets from an IP Set
+    /// Remove an asset/assets from an IP Set.
+    /// `price` is what the IP Set sold the removed IPFs for, it's required when removing any
     pub(crate) fn inner_remove(
         owner: OriginFor<T>,
         ips_id: T::IpId,
         original_caller: Option<T::AccountId>,
         assets: Vec<AnyIdWithNewOwner<T>>,
         new_metadata: Option<Vec<u8>>,
+        price: Option<ipf::BalanceOf<T>>,
     ) -> DispatchResult {
         IpStorage::<T>::try_mutate_exists(ips_id, |ips_info| -> DispatchResult {
             let caller_account = ensure_signed(owner.clone())?;
@@ -424,70 +320,33 @@ impl<T: Config> Pallet<T> {
                 Error::<T>::NoPermission
             );
 
-            // Are any of the assets requested for removal, not in the IP Set?
-            ensure!(
-                !assets
-                    .clone()
-                    .into_iter()
-                    .any(|id| { !info.data.contains(&id.0) }),
-                Error::<T>::NoPermission
-            );
-
-            let mut old_assets = info.data.clone();
-
-            // Checks passed, now send requested assets to new owners
-            for any_id in assets.clone().into_iter() {
-                match any_id {
-                    // Don't do anything. Nested IPS needs rewrite
-                    (AnyId::IpsId(_this_ips_id), _new_owner) => (),
-                    // {
-                    //     IpStorage::<T>::try_mutate_exists(this_ips_id, |ips| -> DispatchResult {
-                    //         let id: (<T as Config>::IpId, Option<<T as Config>::IpId>) =
-                    //             (this_ips_id.into(), None);
-                    //         Pallet::<T>::internal_mint(
-                    //             id,
-                    //             new_owner,
-                    //             <T as Config>::ExistentialDeposit::get(),
-                    //         )?;
-
-                    //         ips.clone().unwrap().parentage =
-                    //             Parentage::Parent(multi_account_id::<T, T::IpId>(
-                    //                 this_ips_id,
-                    //                 None,
-                    //             ));
-
-                    //         Ok(())
-                    //     })?;
-                    // }
-                    (AnyId::IpfId(this_ipf_id), new_owner) => {
-                        ipf::Pallet::<T>::send(ips_account.clone(), this_ipf_id, new_owner)?
+            let ipfs: Vec<<T as ipf::Config>::IpfId> = assets
+                .iter()
+                .filter_map(|(asset, _)| {
+                    if let AnyId::IpfId(ipf_id) = asset {
+                        Some(*ipf_id)
+                    } else {
+                        None
                     }
+                })
+                .collect();
 
-                    (AnyId::RmrkNft((collection_id, nft_id)), new_owner) => {
-                        pallet_rmrk_core::Pallet::<T>::nft_send(
-                            ips_account.clone(),
-                            collection_id,
-                            nft_id,
-                            rmrk_traits::AccountIdOrCollectionNftTuple::AccountId(new_owner),
-                        )?;
-                    }
-                    (AnyId::RmrkCollection(collection_id), new_owner) => {
-                        pallet_rmrk_core::Pallet::<T>::collection_change_issuer(
-                            collection_id,
-                            new_owner.clone(),
-                        )?;
-                    }
-                }
+            let price = if ipfs.is_empty() {
+                Zero::zero()
+            } else {
+                price.ok_or(Error::<T>::PriceRequired)?
+            };
+
+            // Unlisting fails for assets not in the IP Set, then send them to their new owners.
+            // Fungible holdings are checked against `FungibleHoldings` when withdrawn
+            for (asset, new_owner) in assets.clone().into_iter() {
+                Pallet::<T>::unlist_asset(ips_id, &asset)?;
+
+                Pallet::<T>::transfer_asset_from_ips(ips_id, &asset, &ips_account, &new_owner)?;
             }
 
-            // Extract `AnyIdOf`'s from `AnyIdWithNewOwner`'s tuples
-            // Then remove all assets from `old_assets` that were transferred out of the IP Set
-            let just_ids = assets
-                .clone()
-                .into_iter()
-                .map(|(x, _)| x)
-                .collect::<Vec<AnyIdOf<T>>>();
-            old_assets.retain(|x| !just_ids.clone().contains(x));
+            // The IP Set is the seller, so the royalties come out of its account
+            Pallet::<T>::pay_ipf_royalties(&ipfs, &ips_account, price)?;
 
             // Update IP Set info struct in storage
             *ips_info = Some(IpInfo {
@@ -499,7 +358,6 @@ impl<T: Config> Pallet<T> {
                 } else {
                     info.metadata.clone()
                 },
-                data: old_assets,
                 ips_type: info.ips_type,
                 allow_replica: info.allow_replica,
 
@@ -522,8 +380,172 @@ impl<T: Config> Pallet<T> {
         })
     }
 
-    /// Allow replication for the specified IP Set
-    pub(crate) fn inner_allow_replica(owner: OriginFor<T>, ips_id: T::IpId) -> DispatchResult {
+    /// Pay the royalty of each of `ipfs` from `payer`, on its share of the `price` they were sold or licensed for.
+    /// The price is split evenly, the last IPF also gets what is left over from the division
+    fn pay_ipf_royalties(
+        ipfs: &[<T as ipf::Config>::IpfId],
+        payer: &T::AccountId,
+        price: ipf::BalanceOf<T>,
+    ) -> DispatchResult {
+        if let Some((last, rest)) = ipfs.split_last() {
+            let share = price / (ipfs.len() as u32).into();
+
+            for ipf_id in rest {
+                ipf::Pallet::<T>::pay_royalty(*ipf_id, payer, share)?;
+            }
+
+            ipf::Pallet::<T>::pay_royalty(
+                *last,
+                payer,
+                price - share * (rest.len() as u32).into(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// List `asset` in `IpsAssets`, up to `MaxAssetsPerIps` assets per IP Set.
+    /// IPFs get locked under `IPS_LOCK_ID`
+    fn list_asset(ips_id: T::IpId, asset: &AnyIdOf<T>) -> DispatchResult {
+        if !Pallet::<T>::is_listed_asset(asset) {
+            return Ok(());
+        }
+
+        ensure!(
+            !IpsAssets::<T>::contains_key(ips_id, asset),
+            Error::<T>::AssetAlreadyInIps
+        );
+
+        IpsAssetCount::<T>::try_mutate(ips_id, |count| -> DispatchResult {
+            *count = count.checked_add(1).ok_or(Error::<T>::Overflow)?;
+
+            ensure!(
+                *count <= T::MaxAssetsPerIps::get(),
+                Error::<T>::TooManyAssets
+            );
+
+            Ok(())
+        })?;
+
+        IpsAssets::<T>::insert(ips_id, asset, ());
+        AssetIpSets::<T>::insert(asset, ips_id, ());
+
+        // IPFs can only leave the IP Set through `remove`
+        if let AnyId::IpfId(ipf_id) = asset {
+            ipf::Pallet::<T>::lock(*ipf_id, IPS_LOCK_ID)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove `asset` from `IpsAssets`, failing if the IP Set doesn't hold it
+    fn unlist_asset(ips_id: T::IpId, asset: &AnyIdOf<T>) -> DispatchResult {
+        if !Pallet::<T>::is_listed_asset(asset) {
+            return Ok(());
+        }
+
+        IpsAssets::<T>::take(ips_id, asset).ok_or(Error::<T>::NoPermission)?;
+        AssetIpSets::<T>::remove(asset, ips_id);
+
+        // Parent and child IP Sets share an account, so the IPF stays locked while any of them lists it
+        if let AnyId::IpfId(ipf_id) = asset {
+            if AssetIpSets::<T>::iter_prefix(asset).next().is_none() {
+                ipf::Pallet::<T>::unlock(*ipf_id, IPS_LOCK_ID)?;
+            }
+        }
+
+        IpsAssetCount::<T>::mutate(ips_id, |count| *count = count.saturating_sub(1));
+
+        Ok(())
+    }
+
+    /// Track `amount` of `asset_id` as held by the IP Set, moving it from `source` into `ips_account`.
+    /// With no `source` the funds must already be in `ips_account` without being tracked yet,
+    /// by this IP Set or any other one sharing the account.
+    #[cfg(feature = "assets")]
+    pub(crate) fn deposit_fungible(
+        ips_id: T::IpId,
+        ips_account: &T::AccountId,
+        source: Option<&T::AccountId>,
+        asset_id: <T as pallet_assets::Config>::AssetId,
+        amount: <T as pallet_assets::Config>::Balance,
+    ) -> DispatchResult {
+        FungibleHoldings::<T>::try_mutate(ips_id, asset_id, |held| -> DispatchResult {
+            let new_held = held
+                .unwrap_or_default()
+                .checked_add(&amount)
+                .ok_or(Error::<T>::Overflow)?;
+
+            if let Some(source) = source {
+                <pallet_assets::Pallet<T> as Transfer<T::AccountId>>::transfer(
+                    asset_id,
+                    source,
+                    ips_account,
+                    amount,
+                    false,
+                )?;
+            } else {
+                let held_by_others = IpsByOwner::<T>::iter_key_prefix(ips_account)
+                    .filter(|other_ips_id| *other_ips_id != ips_id)
+                    .filter_map(|other_ips_id| FungibleHoldings::<T>::get(other_ips_id, asset_id))
+                    .fold(Zero::zero(), |total: FungibleBalanceOf<T>, other_held| {
+                        total.saturating_add(other_held)
+                    });
+
+                ensure!(
+                    pallet_assets::Pallet::<T>::balance(asset_id, ips_account)
+                        >= new_held
+                            .checked_add(&held_by_others)
+                            .ok_or(Error::<T>::Overflow)?,
+                    Error::<T>::NotEnoughAmount
+                );
+            }
+
+            *held = Some(new_held);
+
+            Ok(())
+        })
+    }
+
+    /// Move `amount` of `asset_id` held by the IP Set out of `ips_account` to `new_owner`
+    #[cfg(feature = "assets")]
+    pub(crate) fn withdraw_fungible(
+        ips_id: T::IpId,
+        ips_account: &T::AccountId,
+        asset_id: <T as pallet_assets::Config>::AssetId,
+        amount: <T as pallet_assets::Config>::Balance,
+        new_owner: &T::AccountId,
+    ) -> DispatchResult {
+        FungibleHoldings::<T>::try_mutate_exists(ips_id, asset_id, |held| -> DispatchResult {
+            let new_held = held
+                .ok_or(Error::<T>::NoPermission)?
+                .checked_sub(&amount)
+                .ok_or(Error::<T>::NotEnoughAmount)?;
+
+            <pallet_assets::Pallet<T> as Transfer<T::AccountId>>::transfer(
+                asset_id,
+                ips_account,
+                new_owner,
+                amount,
+                false,
+            )?;
+
+            *held = if new_held.is_zero() {
+                None
+            } else {
+                Some(new_held)
+            };
+
+            Ok(())
+        })
+    }
+
+    /// Allow replication for the specified IP Set, each replica paying `fee` to it
+    pub(crate) fn inner_allow_replica(
+        owner: OriginFor<T>,
+        ips_id: T::IpId,
+        fee: ipf::BalanceOf<T>,
+    ) -> DispatchResult {
         IpStorage::<T>::try_mutate_exists(ips_id, |ips_info| -> DispatchResult {
             let owner = ensure_signed(owner)?;
             let info = ips_info.take().ok_or(Error::<T>::IpsNotFound)?;
@@ -549,7 +571,6 @@ impl<T: Config> Pallet<T> {
             *ips_info = Some(IpInfo {
                 parentage: info.parentage,
                 metadata: info.metadata,
-                data: info.data,
                 ips_type: info.ips_type,
                 allow_replica: true,
 
@@ -561,7 +582,9 @@ impl<T: Config> Pallet<T> {
                 default_permission: info.default_permission,
             });
 
-            Self::deposit_event(Event::AllowedReplica { ips_id });
+            ReplicaFees::<T>::insert(ips_id, fee);
+
+            Self::deposit_event(Event::AllowedReplica { ips_id, fee });
 
             Ok(())
         })
@@ -594,7 +617,6 @@ impl<T: Config> Pallet<T> {
             *ips_info = Some(IpInfo {
                 parentage: info.parentage,
                 metadata: info.metadata,
-                data: info.data,
                 ips_type: info.ips_type,
                 allow_replica: false,
 
@@ -606,14 +628,17 @@ impl<T: Config> Pallet<T> {
                 default_permission: info.default_permission,
             });
 
+            ReplicaFees::<T>::remove(ips_id);
+
             Self::deposit_event(Event::DisallowedReplica { ips_id });
 
             Ok(())
         })
     }
 
-    /// DISABLED
-    pub(crate) fn _inner_create_replica(
+    /// Create a replica of an IP Set. The creator pays the replica fee to the original IP Set,
+    /// which pays the royalty of its IPFs on it
+    pub(crate) fn inner_create_replica(
         owner: OriginFor<T>,
         original_ips_id: T::IpId,
         ipl_license: <T as Config>::Licenses,
@@ -630,6 +655,34 @@ impl<T: Config> Pallet<T> {
             // Replication must be allowed
             ensure!(original_ips.allow_replica, Error::<T>::ReplicaNotAllowed);
 
+            let original_account = match original_ips.parentage {
+                Parentage::Parent(ips_account) => ips_account,
+                Parentage::Child(_, absolute_parent_account) => absolute_parent_account,
+            };
+
+            // The replica licenses the contents of the original IP Set, whose IPF authors get their royalty on the fee
+            if let Some(fee) = ReplicaFees::<T>::get(original_ips_id) {
+                <T as ipf::Config>::Currency::transfer(
+                    &creator,
+                    &original_account,
+                    fee,
+                    ExistenceRequirement::KeepAlive,
+                )?;
+
+                let ipfs: Vec<<T as ipf::Config>::IpfId> =
+                    IpsAssets::<T>::iter_key_prefix(original_ips_id)
+                        .filter_map(|asset| {
+                            if let AnyId::IpfId(ipf_id) = asset {
+                                Some(ipf_id)
+                            } else {
+                                None
+                            }
+                        })
+                        .collect();
+
+                Pallet::<T>::pay_ipf_royalties(&ipfs, &original_account, fee)?;
+            }
+
             let current_id = *ips_id;
             // Increment counter
             *ips_id = ips_id
@@ -648,8 +701,8 @@ impl<T: Config> Pallet<T> {
 
             let info = IpInfo {
                 parentage: Parentage::Parent(ips_account.clone()),
+                // Contents are read from the original IP Set, so none are listed for the replica
                 metadata: original_ips.metadata,
-                data: original_ips.data,
                 ips_type: IpsType::Replica(original_ips_id),
                 allow_replica: false,
 
@@ -661,17 +714,17 @@ impl<T: Config> Pallet<T> {
                 default_permission: ipl_default_permission,
             };
 
-            // ???
+            // Update core IPS storage
+            IpStorage::<T>::insert(current_id, info);
+            IpsByOwner::<T>::insert(ips_account.clone(), current_id, ());
+
+            // Minting updates the supply of the replica, so it has to be stored first
             Pallet::<T>::internal_mint(
                 (current_id, None),
                 creator,
                 <T as Config>::ExistentialDeposit::get(),
             )?;
 
-            // Update core IPS storage
-            IpStorage::<T>::insert(current_id, info);
-            IpsByOwner::<T>::insert(ips_account.clone(), current_id, ());
-
             Self::deposit_event(Event::ReplicaCreated {
                 ips_account,
                 ips_id: original_ips_id,
@@ -4,25 +4,32 @@ use core::convert::TryInto;
 use frame_support::{
     dispatch::{CallMetadata, Dispatchable, GetCallMetadata, GetDispatchInfo, RawOrigin},
     pallet_prelude::*,
-    traits::WrapperKeepOpaque,
+    traits::{Contains, WrapperKeepOpaque},
     weights::WeightToFee,
 };
 use frame_system::{ensure_signed, pallet_prelude::*};
 use primitives::{OneOrPercent, Parentage, SubIptInfo};
 use sp_arithmetic::traits::Zero;
 use sp_io::hashing::blake2_256;
-use sp_runtime::traits::{CheckedAdd, CheckedDiv, CheckedSub, StaticLookup};
+use sp_runtime::traits::{CheckedAdd, CheckedSub, StaticLookup};
 use sp_std::{boxed::Box, vec, vec::Vec};
 
 pub type OpaqueCall<T> = WrapperKeepOpaque<<T as Config>::Call>;
 
+/// Encoded call kept in `CallPreimages` while a multisig operation is in the voting stage
+pub type BoundedCallBytes<T> = BoundedVec<u8, <T as Config>::MaxCallSize>;
+
+/// Unique (per IP Set) identifier of a multisig operation
+pub type MultisigId = u32;
+
 /// Details of a multisig operation
 #[derive(Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
-pub struct MultisigOperation<AccountId, Signers, Call, Metadata> {
+pub struct MultisigOperation<AccountId, Signers, Metadata> {
     signers: Signers,
     include_original_caller: bool,
     original_caller: AccountId,
-    actual_call: Call,
+    /// Hash of the call, which is kept in `CallPreimages` until the operation is executed or canceled
+    call_hash: [u8; 32],
     call_metadata: [u8; 2],
     call_weight: Weight,
     metadata: Option<Metadata>,
@@ -35,15 +42,26 @@ pub type MultisigOperationOf<T> = MultisigOperation<
             <T as frame_system::Config>::AccountId,
             // Token account voted with???
             Option<<T as pallet::Config>::IpId>,
+            // Fee reserved from the voter, paid to the IP Set account once the call executes
+            <T as pallet::Config>::Balance,
         ),
         <T as Config>::MaxCallers,
     >,
-    OpaqueCall<T>,
     BoundedVec<u8, <T as pallet::Config>::MaxMetadata>,
 >;
 
+pub type SubIptInfoOf<T> = SubIptInfo<
+    <T as pallet::Config>::IpId,
+    BoundedVec<u8, <T as pallet::Config>::MaxMetadata>,
+    <T as pallet::Config>::Balance,
+>;
+
+/// Sub tokens to create: (sub token ID, metadata) and the account endowed with their initial supply
 pub type SubAssetsWithEndowment<T> = Vec<(
-    SubIptInfo<<T as pallet::Config>::IpId, BoundedVec<u8, <T as pallet::Config>::MaxMetadata>>,
+    (
+        <T as pallet::Config>::IpId,
+        BoundedVec<u8, <T as pallet::Config>::MaxMetadata>,
+    ),
     (
         <T as frame_system::Config>::AccountId,
         <T as pallet::Config>::Balance,
@@ -141,23 +159,8 @@ impl<T: Config> Pallet<T> {
     ) -> DispatchResultWithPostInfo {
         let owner = ensure_signed(caller.clone())?;
 
-        // These extrinsics must be called only through InvArch functions or storage will become out of sync
-        ensure!(
-            !matches!(
-                call.get_call_metadata(),
-                CallMetadata {
-                    pallet_name: "RmrkCore",
-                    function_name: "send"
-                        | "burn_nft"
-                        | "destroy_collection"
-                        | "change_collection_issuer",
-                } | CallMetadata {
-                    pallet_name: "Ipf",
-                    function_name: "burn"
-                }
-            ),
-            Error::<T>::CantExecuteThisCall
-        );
+        // Catch calls that can never succeed before any votes or fees are collected
+        let call_metadata: [u8; 2] = Pallet::<T>::validate_multisig_call(ipt_id, &call)?;
 
         // Get IPS/IPT info
         let ipt = IpStorage::<T>::get(ipt_id.0).ok_or(Error::<T>::IpDoesntExist)?;
@@ -175,16 +178,13 @@ impl<T: Config> Pallet<T> {
         let total_issuance = ipt.supply
             + SubAssets::<T>::iter_prefix_values(ipt_id.0)
                 .map(|sub_asset| {
-                    let supply =
-                        Balance::<T>::iter_prefix_values((ipt_id.0, Some(sub_asset.id))).sum();
-
                     // Take into account that some sub tokens have full weight while others may have partial weight or none at all
                     if let OneOrPercent::ZeroPoint(weight) =
                         Pallet::<T>::asset_weight(ipt_id.0, sub_asset.id)?
                     {
-                        Some(weight * supply)
+                        Some(weight * sub_asset.supply)
                     } else {
-                        Some(supply)
+                        Some(sub_asset.supply)
                     }
                 })
                 .collect::<Option<Vec<<T as pallet::Config>::Balance>>>()
@@ -202,23 +202,10 @@ impl<T: Config> Pallet<T> {
                 total_issuance
             };
 
-        // Get call metadata
-        let call_metadata: [u8; 2] = call
-            .encode()
-            .split_at(2)
-            .0
-            .try_into()
-            .map_err(|_| Error::<T>::CallHasTooFewBytes)?;
-
         // Get caller balance of `ipt_id` token, weight adjusted
         let owner_balance: <T as Config>::Balance = if let OneOrPercent::ZeroPoint(percent) = {
             // Function called with some sub token
             if let Some(sub_asset) = ipt_id.1 {
-                ensure!(
-                    Pallet::<T>::has_permission(ipt_id.0, sub_asset, call_metadata,)?,
-                    Error::<T>::SubAssetHasNoPermission
-                );
-
                 Pallet::<T>::asset_weight(ipt_id.0, sub_asset).ok_or(Error::<T>::IpDoesntExist)?
             } else {
                 // Function called with IPT0 token
@@ -237,36 +224,61 @@ impl<T: Config> Pallet<T> {
         // Compute the `call` hash
         let call_hash: [u8; 32] = blake2_256(&call.encode());
 
-        // Ensure that this exact `call` has not been executed before???
-        ensure!(
-            Multisig::<T>::get(ipt_id.0, call_hash).is_none(),
-            Error::<T>::MultisigOperationAlreadyExists
-        );
+        // Reserve a new multisig ID for this IP Set, so identical calls can be proposed more than once
+        let multisig_id =
+            NextMultisigId::<T>::try_mutate(ipt_id.0, |id| -> Result<MultisigId, DispatchError> {
+                let current_id = *id;
+                *id = id.checked_add(1).ok_or(Error::<T>::NoAvailableMultisigId)?;
+                Ok(current_id)
+            })?;
+
+        // Calculate fee from the `call` weight
+        let fee: <T as pallet::Config>::Balance =
+            T::WeightToFee::weight_to_fee(&call.get_dispatch_info().weight).into();
+
+        // Sub tokens with a spend allowance can transfer funds out of the IP Set account without a vote,
+        // as long as the transfer fits in what is left of the allowance for the current period.
+        // This includes sub tokens with no voting weight at all
+        let allowance_spend = if owner_balance >= total_per_threshold {
+            None
+        } else {
+            Pallet::<T>::covered_by_spend_allowance(ipt_id, include_caller, &call)
+        };
 
         // If `caller` has enough balance to meet/exeed the threshold, then go ahead and execute the `call` now.
-        if owner_balance >= total_per_threshold {
-            // Transfer the extrinsic fee for `call` from `caller` to the IP Set account
-            pallet_balances::Pallet::<T>::transfer(
-                caller,
-                // Recompute IP Set AccountId
-                <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(
-                    derive_ips_account::<T>(ipt_id.0, None),
-                ),
-                // Calculate fee from the `call` weight
-                <T as pallet::Config>::Balance::from(T::WeightToFee::weight_to_fee(
-                    &call.get_dispatch_info().weight,
-                ))
-                .into(),
-            )?;
+        if owner_balance >= total_per_threshold || allowance_spend.is_some() {
+            // Reserve the extrinsic fee for `call` from `caller`, it's paid to the IP Set account if the call succeeds
+            let fee_paid = T::FeeHandler::execution_fee(fee, Zero::zero());
+            Pallet::<T>::reserve_multisig_fee(&owner, fee_paid)?;
 
             // Actually dispatch this call and return the result of it
-            let dispatch_result = call.dispatch(
-                RawOrigin::Signed(derive_ips_account::<T>(
-                    ipt_id.0,
-                    if include_caller { Some(&owner) } else { None },
-                ))
-                .into(),
-            );
+            let dispatch_result = call
+                .dispatch(
+                    RawOrigin::Signed(derive_ips_account::<T>(
+                        ipt_id.0,
+                        if include_caller { Some(&owner) } else { None },
+                    ))
+                    .into(),
+                )
+                .map(|_| ())
+                .map_err(|error_with_post_info| error_with_post_info.error);
+
+            // The call failed, so `caller` gets the fee back
+            if dispatch_result.is_err() {
+                Pallet::<T>::refund_multisig_fee(&owner, fee_paid);
+            } else {
+                // The sub account is only registered once a call was successfully dispatched from it
+                if include_caller {
+                    Pallet::<T>::note_sub_account(ipt_id.0, owner.clone());
+                }
+
+                Pallet::<T>::collect_multisig_fee(ipt_id.0, &owner, fee_paid)?;
+                Pallet::<T>::charge_ips_fee(ipt_id.0, T::FeeHandler::ips_fee(fee))?;
+
+                if let (Some(amount), Some(sub_token_id)) = (allowance_spend, ipt_id.1) {
+                    Pallet::<T>::record_spend(ipt_id.0, sub_token_id, amount)?;
+                }
+            }
 
             Self::deposit_event(Event::MultisigExecuted {
                 ips_id: ipt_id.0,
@@ -275,42 +287,37 @@ impl<T: Config> Pallet<T> {
                     if include_caller { Some(&owner) } else { None },
                 ),
                 voter: owner,
+                multisig_id,
                 call_hash,
                 call: opaque_call,
-                result: dispatch_result.is_ok(),
+                result: dispatch_result,
             });
         } else {
             // `caller` does not have enough balance to execute.
-            if owner_balance > Zero::zero() {
-                // Transfer the `caller`s portion of the extrinsic fee to the IP Set account
-                pallet_balances::Pallet::<T>::transfer(
-                    caller,
-                    <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(
-                        derive_ips_account::<T>(ipt_id.0, None),
-                    ),
-                    // `caller`s balance is x percent of `total_per_threshold`,
-                    // So they pay x percent of the fee
-                    <T as pallet::Config>::Balance::from(
-                        (T::WeightToFee::weight_to_fee(&call.get_dispatch_info().weight)
-                            .checked_div(&total_per_threshold.into())
-                            .ok_or(Error::<T>::DivisionByZero)?)
-                            * owner_balance.into(),
-                    )
-                    .into(),
-                )?;
-            }
+            // The call is kept encoded in `CallPreimages` during the vote, so it has to fit in `MaxCallSize`
+            ensure!(
+                call.encoded_size() <= T::MaxCallSize::get() as usize,
+                Error::<T>::CallTooLarge
+            );
+
+            // Reserve the `caller`s portion of the extrinsic fee until the call is executed
+            let fee_paid = T::FeeHandler::vote_fee(fee, owner_balance, total_per_threshold, true);
+            Pallet::<T>::reserve_multisig_fee(&owner, fee_paid)?;
+
+            // The call bytes are noted once per call hash, `caller` pays the deposit if they are new
+            Pallet::<T>::note_call_preimage(&owner, call_hash, call.encode())?;
 
             // Multisig call is now in the voting stage, so update storage.
             Multisig::<T>::insert(
                 ipt_id.0,
-                call_hash,
+                multisig_id,
                 MultisigOperation {
-                    signers: vec![(owner.clone(), ipt_id.1)]
+                    signers: vec![(owner.clone(), ipt_id.1, fee_paid)]
                         .try_into()
                         .map_err(|_| Error::<T>::TooManySignatories)?,
                     include_original_caller: include_caller,
                     original_caller: owner.clone(),
-                    actual_call: opaque_call.clone(),
+                    call_hash,
                     call_metadata,
                     call_weight: call.get_dispatch_info().weight,
                     metadata: bounded_metadata,
@@ -326,6 +333,7 @@ impl<T: Config> Pallet<T> {
                 voter: owner,
                 votes_added: owner_balance,
                 votes_required: total_per_threshold,
+                multisig_id,
                 call_hash,
                 call: opaque_call,
             });
@@ -334,13 +342,84 @@ impl<T: Config> Pallet<T> {
         Ok(().into())
     }
 
+    /// Checks that `call` can be dispatched by the IP Set multisig, so calls that would fail are rejected
+    /// before votes and fees are collected. Returns the call metadata (pallet index, function index).
+    pub(crate) fn validate_multisig_call(
+        ipt_id: (T::IpId, Option<T::IpId>),
+        call: &<T as pallet::Config>::Call,
+    ) -> Result<[u8; 2], DispatchError> {
+        // These extrinsics must be called only through InvArch functions or storage will become out of sync
+        ensure!(
+            !matches!(
+                call.get_call_metadata(),
+                CallMetadata {
+                    pallet_name: "RmrkCore",
+                    function_name: "send"
+                        | "burn_nft"
+                        | "destroy_collection"
+                        | "change_collection_issuer",
+                } | CallMetadata {
+                    pallet_name: "Uniques",
+                    function_name: "transfer" | "burn",
+                } | CallMetadata {
+                    // Anything moving or burning the balance of the IP Set account, which `FungibleHoldings` tracks
+                    pallet_name: "Assets",
+                    function_name: "transfer"
+                        | "transfer_keep_alive"
+                        | "force_transfer"
+                        | "approve_transfer"
+                        | "transfer_approved"
+                        | "burn"
+                        | "refund"
+                        | "destroy",
+                }
+            ),
+            Error::<T>::CantExecuteThisCall
+        );
+
+        // The runtime call filter is applied to the IP Set origin on dispatch, so a filtered call would always fail
+        ensure!(
+            <T as frame_system::Config>::BaseCallFilter::contains(call.into_ref()),
+            Error::<T>::CallFiltered
+        );
+
+        let call_metadata = Pallet::<T>::call_metadata(call)?;
+
+        // Function called with some sub token, which must exist and be allowed to call this function
+        if let Some(sub_asset) = ipt_id.1 {
+            ensure!(
+                SubAssets::<T>::contains_key(ipt_id.0, sub_asset),
+                Error::<T>::SubAssetNotFound
+            );
+
+            ensure!(
+                Pallet::<T>::has_permission(ipt_id.0, sub_asset, call_metadata)?,
+                Error::<T>::SubAssetHasNoPermission
+            );
+        }
+
+        Ok(call_metadata)
+    }
+
+    /// Get the call metadata: 1st byte = pallet index, 2nd byte = function index
+    pub(crate) fn call_metadata(
+        call: &<T as pallet::Config>::Call,
+    ) -> Result<[u8; 2], DispatchError> {
+        Ok(call
+            .encode()
+            .split_at(2)
+            .0
+            .try_into()
+            .map_err(|_| Error::<T>::CallHasTooFewBytes)?)
+    }
+
     /// Vote on a multisig transaction that has not been executed yet
     pub(crate) fn inner_vote_multisig(
         caller: OriginFor<T>,
         ipt_id: (T::IpId, Option<T::IpId>),
-        call_hash: [u8; 32],
+        multisig_id: MultisigId,
     ) -> DispatchResultWithPostInfo {
-        Multisig::<T>::try_mutate_exists(ipt_id.0, call_hash, |data| {
+        Multisig::<T>::try_mutate_exists(ipt_id.0, multisig_id, |data| {
             let owner = ensure_signed(caller.clone())?;
 
             let ipt = IpStorage::<T>::get(ipt_id.0).ok_or(Error::<T>::IpDoesntExist)?;
@@ -376,7 +455,7 @@ impl<T: Config> Pallet<T> {
                 .signers
                 .clone()
                 .into_iter()
-                .map(|(voter, asset): (T::AccountId, Option<T::IpId>)| {
+                .map(|(voter, asset, _)| {
                     Balance::<T>::get((ipt_id.0, asset), voter).map(|balance| {
                         if let OneOrPercent::ZeroPoint(percent) = if let Some(sub_asset) = asset {
                             Pallet::<T>::asset_weight(ipt_id.0, sub_asset).unwrap()
@@ -398,15 +477,12 @@ impl<T: Config> Pallet<T> {
             let total_issuance = ipt.supply
                 + SubAssets::<T>::iter_prefix_values(ipt_id.0)
                     .map(|sub_asset| {
-                        let supply =
-                            Balance::<T>::iter_prefix_values((ipt_id.0, Some(sub_asset.id))).sum();
-
                         if let OneOrPercent::ZeroPoint(weight) =
                             Pallet::<T>::asset_weight(ipt_id.0, sub_asset.id)?
                         {
-                            Some(weight * supply)
+                            Some(weight * sub_asset.supply)
                         } else {
-                            Some(supply)
+                            Some(sub_asset.supply)
                         }
                     })
                     .collect::<Option<Vec<<T as pallet::Config>::Balance>>>()
@@ -430,31 +506,25 @@ impl<T: Config> Pallet<T> {
 
             // If already cast votes + `caller` weighted votes are enough to meet/exeed the threshold, then go ahead and execute the `call` now.
             if (total_in_operation + voter_balance) >= total_per_threshold {
-                // Transfer the extrinsic fee for `call` from `caller` to the IP Set account
-                pallet_balances::Pallet::<T>::transfer(
-                    caller,
-                    <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(
-                        derive_ips_account::<T>(ipt_id.0, None),
-                    ),
-                    // Voter will pay the remainder of the fee after subtracting the total IPTs already in the operation converted to real fee value.
-                    fee.checked_sub(
-                        &(total_in_operation
-                            * (fee
-                                .checked_div(&total_per_threshold)
-                                .ok_or(Error::<T>::DivisionByZero)?)),
-                    )
-                    .ok_or(Error::<T>::NotEnoughAmount)?
-                    .into(),
-                )?;
+                // Voter will pay the remainder of the fee after subtracting what previous voters already paid
+                let already_paid: <T as pallet::Config>::Balance = old_data
+                    .signers
+                    .iter()
+                    .map(|(_, _, fee_paid)| *fee_paid)
+                    .sum();
+                let fee_paid = T::FeeHandler::execution_fee(fee, already_paid);
+                Pallet::<T>::reserve_multisig_fee(&owner, fee_paid)?;
 
                 // Multisig storage records are removed when the transaction is executed or the vote on the transaction is withdrawn
                 *data = None;
 
+                // The call is only fetched now that it's being executed
+                let call = Pallet::<T>::call_preimage(old_data.call_hash)?;
+                let opaque_call: OpaqueCall<T> = WrapperKeepOpaque::from_encoded(call.encode());
+                Pallet::<T>::unnote_call_preimage(old_data.call_hash);
+
                 // Actually dispatch this call and return the result of it
-                let dispatch_result = old_data
-                    .actual_call
-                    .try_decode()
-                    .ok_or(Error::<T>::CouldntDecodeCall)?
+                let dispatch_result = call
                     .dispatch(
                         RawOrigin::Signed(derive_ips_account::<T>(
                             ipt_id.0,
@@ -465,7 +535,30 @@ impl<T: Config> Pallet<T> {
                             },
                         ))
                         .into(),
-                    );
+                    )
+                    .map(|_| ())
+                    .map_err(|error_with_post_info| error_with_post_info.error);
+
+                // The call failed, so every voter gets their portion of the fee back,
+                // otherwise the fees are paid to the IP Set account
+                if dispatch_result.is_err() {
+                    for (signer, _, signer_fee_paid) in old_data.signers.iter() {
+                        Pallet::<T>::refund_multisig_fee(signer, *signer_fee_paid);
+                    }
+
+                    Pallet::<T>::refund_multisig_fee(&owner, fee_paid);
+                } else {
+                    if old_data.include_original_caller {
+                        Pallet::<T>::note_sub_account(ipt_id.0, old_data.original_caller.clone());
+                    }
+
+                    for (signer, _, signer_fee_paid) in old_data.signers.iter() {
+                        Pallet::<T>::collect_multisig_fee(ipt_id.0, signer, *signer_fee_paid)?;
+                    }
+
+                    Pallet::<T>::collect_multisig_fee(ipt_id.0, &owner, fee_paid)?;
+                    Pallet::<T>::charge_ips_fee(ipt_id.0, T::FeeHandler::ips_fee(fee))?;
+                }
 
                 Self::deposit_event(Event::MultisigExecuted {
                     ips_id: ipt_id.0,
@@ -478,35 +571,22 @@ impl<T: Config> Pallet<T> {
                         },
                     ),
                     voter: owner,
-                    call_hash,
-                    call: old_data.actual_call,
-                    result: dispatch_result.is_ok(),
+                    multisig_id,
+                    call_hash: old_data.call_hash,
+                    call: opaque_call,
+                    result: dispatch_result,
                 });
             } else {
                 // `caller`s votes were not enough to pass the vote
-                if voter_balance > Zero::zero() {
-                    // Transfer the callers portion of the transaction fee to the IP Set account
-                    pallet_balances::Pallet::<T>::transfer(
-                        caller,
-                        <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(
-                            derive_ips_account::<T>(ipt_id.0, None),
-                        ),
-                        // callers balance is x percent of `total_per_threshold`,
-                        // So they pay x percent of the fee
-                        <T as pallet::Config>::Balance::from(
-                            (T::WeightToFee::weight_to_fee(&old_data.call_weight)
-                                .checked_div(&total_per_threshold.into())
-                                .ok_or(Error::<T>::DivisionByZero)?)
-                                * voter_balance.into(),
-                        )
-                        .into(),
-                    )?;
-                }
+                // Reserve the callers portion of the transaction fee until the call is executed
+                let fee_paid =
+                    T::FeeHandler::vote_fee(fee, voter_balance, total_per_threshold, false);
+                Pallet::<T>::reserve_multisig_fee(&owner, fee_paid)?;
 
                 // Update storage
                 old_data.signers = {
                     let mut v = old_data.signers.to_vec();
-                    v.push((owner.clone(), ipt_id.1));
+                    v.push((owner.clone(), ipt_id.1, fee_paid));
                     v.try_into().map_err(|_| Error::<T>::MaxMetadataExceeded)?
                 };
                 *data = Some(old_data.clone());
@@ -525,8 +605,8 @@ impl<T: Config> Pallet<T> {
                     votes_added: voter_balance,
                     current_votes: (total_in_operation + voter_balance),
                     votes_required: total_per_threshold,
-                    call_hash,
-                    call: old_data.actual_call,
+                    multisig_id,
+                    call_hash: old_data.call_hash,
                 });
             }
 
@@ -538,9 +618,9 @@ impl<T: Config> Pallet<T> {
     pub(crate) fn inner_withdraw_vote_multisig(
         caller: OriginFor<T>,
         ipt_id: (T::IpId, Option<T::IpId>),
-        call_hash: [u8; 32],
+        multisig_id: MultisigId,
     ) -> DispatchResultWithPostInfo {
-        Multisig::<T>::try_mutate_exists(ipt_id.0, call_hash, |data| {
+        Multisig::<T>::try_mutate_exists(ipt_id.0, multisig_id, |data| {
             let owner = ensure_signed(caller.clone())?;
 
             let ipt = IpStorage::<T>::get(ipt_id.0).ok_or(Error::<T>::IpDoesntExist)?;
@@ -557,61 +637,14 @@ impl<T: Config> Pallet<T> {
 
             // if `caller` is the account who created this vote, they can dissolve it immediately
             if owner == old_data.original_caller {
-                // Get total IP Set token issuance (IPT0 + all sub tokens), weight adjusted (meaning `ZeroPoint(0)` tokens count for 0)
-                let total_issuance = ipt.supply
-                    + SubAssets::<T>::iter_prefix_values(ipt_id.0)
-                        .map(|sub_asset| {
-                            let supply =
-                                Balance::<T>::iter_prefix_values((ipt_id.0, Some(sub_asset.id)))
-                                    .sum();
-
-                            if let OneOrPercent::ZeroPoint(weight) =
-                                Pallet::<T>::asset_weight(ipt_id.0, sub_asset.id)?
-                            {
-                                Some(weight * supply)
-                            } else {
-                                Some(supply)
-                            }
-                        })
-                        .collect::<Option<Vec<<T as pallet::Config>::Balance>>>()
-                        .ok_or(Error::<T>::IpDoesntExist)?
-                        .into_iter()
-                        .sum();
-
-                // Get minimum # of votes (tokens w/non-zero weight) required to execute a multisig call.
-                let total_per_threshold: <T as pallet::Config>::Balance =
-                    if let OneOrPercent::ZeroPoint(percent) =
-                        Pallet::<T>::execution_threshold(ipt_id.0)
-                            .ok_or(Error::<T>::IpDoesntExist)?
-                    {
-                        percent * total_issuance
-                    } else {
-                        total_issuance
-                    };
-
-                // Send funds held in IPS account for the transaction fee back to the individual signers
-                for signer in old_data.signers {
-                    pallet_balances::Pallet::<T>::transfer(
-                        <T as frame_system::Config>::Origin::from(RawOrigin::Signed(
-                            derive_ips_account::<T>(ipt_id.0, None),
-                        )),
-                        <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(
-                            signer.0.clone(),
-                        ),
-                        <T as pallet::Config>::Balance::from(
-                            (T::WeightToFee::weight_to_fee(&old_data.call_weight)
-                                .checked_div(&total_per_threshold.into())
-                                .ok_or(Error::<T>::DivisionByZero)?)
-                                * Balance::<T>::get((ipt_id.0, signer.1), signer.0)
-                                    .ok_or(Error::<T>::UnknownError)?
-                                    .into(),
-                        )
-                        .into(),
-                    )?;
+                // Unreserve the transaction fee of the individual signers
+                for (signer, _, signer_fee_paid) in old_data.signers.iter() {
+                    Pallet::<T>::refund_multisig_fee(signer, *signer_fee_paid);
                 }
 
                 // Multisig storage records are removed when the transaction is executed or the vote on the transaction is withdrawn
                 *data = None;
+                Pallet::<T>::unnote_call_preimage(old_data.call_hash);
 
                 Self::deposit_event(Event::MultisigCanceled {
                     ips_id: ipt_id.0,
@@ -623,7 +656,8 @@ impl<T: Config> Pallet<T> {
                             None
                         },
                     ),
-                    call_hash,
+                    multisig_id,
+                    call_hash: old_data.call_hash,
                 });
             } else {
                 // caller is not the creator of this vote
@@ -647,16 +681,12 @@ impl<T: Config> Pallet<T> {
                 let total_issuance = ipt.supply
                     + SubAssets::<T>::iter_prefix_values(ipt_id.0)
                         .map(|sub_asset| {
-                            let supply =
-                                Balance::<T>::iter_prefix_values((ipt_id.0, Some(sub_asset.id)))
-                                    .sum();
-
                             if let OneOrPercent::ZeroPoint(weight) =
                                 Pallet::<T>::asset_weight(ipt_id.0, sub_asset.id)?
                             {
-                                Some(weight * supply)
+                                Some(weight * sub_asset.supply)
                             } else {
-                                Some(supply)
+                                Some(sub_asset.supply)
                             }
                         })
                         .collect::<Option<Vec<<T as pallet::Config>::Balance>>>()
@@ -675,29 +705,25 @@ impl<T: Config> Pallet<T> {
                         total_issuance
                     };
 
+                // Sum of the fees the caller paid towards this multisig call
+                let fee_paid: <T as pallet::Config>::Balance = old_data
+                    .signers
+                    .iter()
+                    .filter(|signer| signer.0 == owner)
+                    .map(|(_, _, signer_fee_paid)| *signer_fee_paid)
+                    .sum();
+
                 // Remove caller from the list of signers
                 old_data.signers = old_data
                     .signers
                     .into_iter()
                     .filter(|signer| signer.0 != owner)
-                    .collect::<Vec<(T::AccountId, Option<T::IpId>)>>()
+                    .collect::<Vec<_>>()
                     .try_into()
                     .map_err(|_| Error::<T>::TooManySignatories)?;
 
-                // Transfer the callers portion of the transaction fee from the IP Set account back to the caller
-                pallet_balances::Pallet::<T>::transfer(
-                    <T as frame_system::Config>::Origin::from(RawOrigin::Signed(
-                        derive_ips_account::<T>(ipt_id.0, None),
-                    )),
-                    <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(owner.clone()),
-                    <T as pallet::Config>::Balance::from(
-                        (T::WeightToFee::weight_to_fee(&old_data.call_weight)
-                            .checked_div(&total_per_threshold.into())
-                            .ok_or(Error::<T>::DivisionByZero)?)
-                            * voter_balance.into(),
-                    )
-                    .into(),
-                )?;
+                // Unreserve the callers portion of the transaction fee
+                Pallet::<T>::refund_multisig_fee(&owner, fee_paid);
 
                 *data = Some(old_data.clone());
 
@@ -714,8 +740,8 @@ impl<T: Config> Pallet<T> {
                     voter: owner,
                     votes_removed: voter_balance,
                     votes_required: total_per_threshold,
-                    call_hash,
-                    call: old_data.actual_call,
+                    multisig_id,
+                    call_hash: old_data.call_hash,
                 });
             }
 
@@ -723,6 +749,55 @@ impl<T: Config> Pallet<T> {
         })
     }
 
+    /// Register the account derived from (`ips_id`, `original_caller`) the first time a multisig call is dispatched from it
+    pub(crate) fn note_sub_account(ips_id: T::IpId, original_caller: T::AccountId) {
+        if !SubAccounts::<T>::contains_key(ips_id, &original_caller) {
+            let sub_account = derive_ips_account::<T>(ips_id, Some(&original_caller));
+
+            SubAccounts::<T>::insert(ips_id, &original_caller, &sub_account);
+
+            Self::deposit_event(Event::SubAccountCreated {
+                ips_id,
+                original_caller,
+                sub_account,
+            });
+        }
+    }
+
+    /// Transfer the whole balance of an `include_caller` sub account back to the main IP Set account and close it
+    pub(crate) fn inner_sweep_sub_account(
+        caller: OriginFor<T>,
+        ips_id: T::IpId,
+        original_caller: T::AccountId,
+    ) -> DispatchResult {
+        let caller = ensure_signed(caller)?;
+
+        let ips_account = derive_ips_account::<T>(ips_id, None);
+
+        // Only the IP Set itself or the account the sub account was derived from can sweep it
+        ensure!(
+            caller == ips_account || caller == original_caller,
+            Error::<T>::NoPermission
+        );
+
+        let sub_account = SubAccounts::<T>::take(ips_id, &original_caller)
+            .ok_or(Error::<T>::SubAccountNotFound)?;
+
+        pallet_balances::Pallet::<T>::transfer_all(
+            <T as frame_system::Config>::Origin::from(RawOrigin::Signed(sub_account.clone())),
+            <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(ips_account),
+            false,
+        )?;
+
+        Self::deposit_event(Event::SubAccountSwept {
+            ips_id,
+            original_caller,
+            sub_account,
+        });
+
+        Ok(())
+    }
+
     /// Create one or more sub tokens for an IP Set
     pub(crate) fn inner_create_sub_token(
         caller: OriginFor<T>,
@@ -744,21 +819,29 @@ impl<T: Config> Pallet<T> {
             }
 
             // Create sub tokens, if none already exist
-            for sub in sub_tokens.clone() {
+            for ((id, metadata), (endowed, amount)) in sub_tokens.clone() {
                 ensure!(
-                    !SubAssets::<T>::contains_key(ipt_id, sub.0.id),
+                    !SubAssets::<T>::contains_key(ipt_id, id),
                     Error::<T>::SubAssetAlreadyExists
                 );
 
-                SubAssets::<T>::insert(ipt_id, sub.0.id, &sub.0);
+                SubAssets::<T>::insert(
+                    ipt_id,
+                    id,
+                    SubIptInfo {
+                        id,
+                        metadata,
+                        supply: amount,
+                    },
+                );
 
-                Balance::<T>::insert((ipt_id, Some(sub.0.id)), sub.1 .0, sub.1 .1);
+                Balance::<T>::insert((ipt_id, Some(id)), endowed, amount);
             }
 
             Self::deposit_event(Event::SubTokenCreated {
                 sub_tokens_with_endowment: sub_tokens
                     .into_iter()
-                    .map(|sub| ((ipt_id, sub.0.id), sub.1 .0, sub.1 .1))
+                    .map(|((id, _), (endowed, amount))| ((ipt_id, id), endowed, amount))
                     .collect(),
             });
 
@@ -784,8 +867,17 @@ impl<T: Config> Pallet<T> {
 
                 let mut old_ipt = ipt.take().ok_or(Error::<T>::IpDoesntExist)?;
 
-                // If minting IPT0 tokens, update supply
-                if ipt_id.1.is_none() {
+                // Update the supply of IPT0 or of the sub token being minted
+                if let Some(sub_token_id) = ipt_id.1 {
+                    SubAssets::<T>::try_mutate(ipt_id.0, sub_token_id, |sub| -> DispatchResult {
+                        let sub = sub.as_mut().ok_or(Error::<T>::SubAssetNotFound)?;
+                        sub.supply = sub
+                            .supply
+                            .checked_add(&amount)
+                            .ok_or(Error::<T>::Overflow)?;
+                        Ok(())
+                    })?;
+                } else {
                     old_ipt.supply = old_ipt
                         .supply
                         .checked_add(&amount)
@@ -817,8 +909,17 @@ impl<T: Config> Pallet<T> {
 
                 let mut old_ipt = ipt.take().ok_or(Error::<T>::IpDoesntExist)?;
 
-                // If burning IPT0 tokens, update supply
-                if ipt_id.1.is_none() {
+                // Update the supply of IPT0 or of the sub token being burned
+                if let Some(sub_token_id) = ipt_id.1 {
+                    SubAssets::<T>::try_mutate(ipt_id.0, sub_token_id, |sub| -> DispatchResult {
+                        let sub = sub.as_mut().ok_or(Error::<T>::SubAssetNotFound)?;
+                        sub.supply = sub
+                            .supply
+                            .checked_sub(&amount)
+                            .ok_or(Error::<T>::NotEnoughAmount)?;
+                        Ok(())
+                    })?;
+                } else {
                     old_ipt.supply = old_ipt
                         .supply
                         .checked_sub(&amount)
@@ -24,7 +24,7 @@
 use frame_support::{
     dispatch::Dispatchable,
     pallet_prelude::*,
-    traits::{Currency as FSCurrency, Get, GetCallMetadata},
+    traits::{Currency as FSCurrency, Get, GetCallMetadata, IsSubType, NamedReservableCurrency},
     weights::{GetDispatchInfo, PostDispatchInfo, WeightToFee},
     BoundedVec, Parameter,
 };
@@ -37,34 +37,58 @@ use primitives::IpInfo;
 
 pub use pallet::*;
 
+pub mod asset_handler;
+pub mod fee;
 pub mod ipl;
 pub mod ips;
 pub mod ipt;
+pub mod migrations;
+pub mod preimage;
+pub mod treasury;
 pub mod util;
 
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
-    use primitives::{OneOrPercent, SubIptInfo};
+    use primitives::OneOrPercent;
     use scale_info::prelude::fmt::Display;
     use sp_std::iter::Sum;
 
-    pub use super::{ipl, ips, ipt};
+    pub use super::{asset_handler, fee, ipl, ips, ipt, migrations, preimage, treasury};
 
-    use crate::ipl::LicenseList;
+    use crate::{asset_handler::IpsAssetHandler, fee::MultisigFeeHandler, ipl::LicenseList};
 
+    #[cfg(feature = "rmrk")]
     use rmrk_traits::primitives::{CollectionId, NftId};
 
+    /// `pallet_assets` is only required with the `assets` feature
+    #[cfg(feature = "assets")]
+    pub trait AssetsConfig: pallet_assets::Config {}
+    #[cfg(feature = "assets")]
+    impl<T: pallet_assets::Config> AssetsConfig for T {}
+    #[cfg(not(feature = "assets"))]
+    pub trait AssetsConfig {}
+    #[cfg(not(feature = "assets"))]
+    impl<T> AssetsConfig for T {}
+
+    /// `pallet_uniques` is only required with the `uniques` feature
+    #[cfg(feature = "uniques")]
+    pub trait UniquesConfig: pallet_uniques::Config {}
+    #[cfg(feature = "uniques")]
+    impl<T: pallet_uniques::Config> UniquesConfig for T {}
+    #[cfg(not(feature = "uniques"))]
+    pub trait UniquesConfig {}
+    #[cfg(not(feature = "uniques"))]
+    impl<T> UniquesConfig for T {}
+
     #[pallet::config]
     pub trait Config:
-        frame_system::Config
-        + ipf::Config
-        + pallet_balances::Config
-        + pallet_rmrk_core::Config
-        + pallet_uniques::Config<
-            CollectionId = rmrk_traits::primitives::CollectionId,
-            ItemId = rmrk_traits::primitives::NftId,
-        >
+        frame_system::Config + ipf::Config + pallet_balances::Config + AssetsConfig + UniquesConfig
     {
         /// The IPS Pallet Events
         type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
@@ -78,8 +102,12 @@ pub mod pallet {
             + MaxEncodedLen
             + Clone;
 
-        /// Currency
-        type Currency: FSCurrency<Self::AccountId>;
+        /// Currency multisig call fees are reserved from voters in
+        type Currency: NamedReservableCurrency<
+            Self::AccountId,
+            ReserveIdentifier = [u8; 8],
+            Balance = <Self as pallet::Config>::Balance,
+        >;
 
         type Balance: Member
             + Parameter
@@ -105,19 +133,61 @@ pub mod pallet {
             + GetDispatchInfo
             + From<frame_system::Call<Self>>
             + GetCallMetadata
-            + Encode;
+            + Encode
+            + IsType<<Self as frame_system::Config>::Call>
+            + IsSubType<pallet_balances::Call<Self>>;
 
         type WeightToFee: WeightToFee;
 
+        /// How the fee of a multisig call is split between its voters
+        type FeeHandler: MultisigFeeHandler<Self>;
+
+        /// Handles IP Files placed in an IP Set
+        type IpfAssets: IpsAssetHandler<Self, <Self as ipf::Config>::IpfId>;
+
+        /// Handles RMRK NFTs placed in an IP Set
+        #[cfg(feature = "rmrk")]
+        type RmrkNftAssets: IpsAssetHandler<Self, (CollectionId, NftId)>;
+
+        /// Handles RMRK collections placed in an IP Set
+        #[cfg(feature = "rmrk")]
+        type RmrkCollectionAssets: IpsAssetHandler<Self, CollectionId>;
+
+        /// Handles `pallet_uniques` items placed in an IP Set
+        #[cfg(feature = "uniques")]
+        type UniquesAssets: IpsAssetHandler<
+            Self,
+            (
+                <Self as pallet_uniques::Config>::CollectionId,
+                <Self as pallet_uniques::Config>::ItemId,
+            ),
+        >;
+
         /// The maximum numbers of caller accounts on a single Multisig call
         #[pallet::constant]
         type MaxCallers: Get<u32>;
 
+        /// The maximum size of an encoded call kept in storage while a multisig vote is ongoing
+        #[pallet::constant]
+        type MaxCallSize: Get<u32>;
+
+        /// Deposit reserved for storing the call of a multisig operation
+        #[pallet::constant]
+        type PreimageBaseDeposit: Get<<Self as pallet::Config>::Balance>;
+
+        /// Deposit reserved per byte of the stored call of a multisig operation
+        #[pallet::constant]
+        type PreimageByteDeposit: Get<<Self as pallet::Config>::Balance>;
+
         #[pallet::constant]
         type MaxSubAssets: Get<u32>;
 
         #[pallet::constant]
         type MaxMetadata: Get<u32>;
+
+        /// The maximum number of assets a single IP Set can directly own
+        #[pallet::constant]
+        type MaxAssetsPerIps: Get<u32>;
     }
 
     pub type BalanceOf<T> =
@@ -125,7 +195,6 @@ pub mod pallet {
 
     pub type IpInfoOf<T> = IpInfo<
         <T as frame_system::Config>::AccountId,
-        BoundedVec<AnyIdOf<T>, <T as Config>::MaxMetadata>,
         ips::IpsMetadataOf<T>,
         <T as Config>::IpId,
         <T as Config>::Balance,
@@ -134,21 +203,106 @@ pub mod pallet {
     >;
 
     /// Valid types that an IP Set can hold
+    ///
+    /// RMRK variants need the `rmrk` feature, `pallet_uniques` items the `uniques` feature (enabled by `rmrk`)
+    /// and fungible assets the `assets` feature.
     #[derive(Encode, Decode, Clone, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
-    pub enum AnyId<IpsId, IpfId, RmrkNftTuple, RmrkCollectionId> {
+    pub enum AnyId<
+        IpsId,
+        IpfId,
+        #[cfg(feature = "rmrk")] RmrkNftTuple,
+        #[cfg(feature = "rmrk")] RmrkCollectionId,
+        #[cfg(feature = "uniques")] UniquesCollectionId,
+        #[cfg(feature = "uniques")] UniquesItemId,
+        #[cfg(feature = "assets")] FungibleAssetId,
+        #[cfg(feature = "assets")] FungibleBalance,
+    > {
+        // Indexes are fixed so the encoding doesn't depend on enabled features
+        #[codec(index = 0)]
         IpfId(IpfId),
+        #[cfg(feature = "rmrk")]
+        #[codec(index = 1)]
         RmrkNft(RmrkNftTuple),
+        #[cfg(feature = "rmrk")]
+        #[codec(index = 2)]
         RmrkCollection(RmrkCollectionId),
+        #[codec(index = 3)]
         IpsId(IpsId),
+        #[cfg(feature = "uniques")]
+        #[codec(index = 4)]
+        UniquesItem(UniquesCollectionId, UniquesItemId),
+        /// An amount of a `pallet_assets` asset. Tracked in `FungibleHoldings` instead of `IpsAssets`
+        #[cfg(feature = "assets")]
+        #[codec(index = 5)]
+        FungibleAsset(FungibleAssetId, FungibleBalance),
     }
 
-    pub type AnyIdOf<T> =
-        AnyId<<T as Config>::IpId, <T as ipf::Config>::IpfId, (CollectionId, NftId), CollectionId>;
+    // `rmrk` enables `uniques`, so there is no RMRK without `pallet_uniques`
+    #[cfg(all(feature = "rmrk", feature = "assets"))]
+    pub type AnyIdOf<T> = AnyId<
+        <T as Config>::IpId,
+        <T as ipf::Config>::IpfId,
+        (CollectionId, NftId),
+        CollectionId,
+        <T as pallet_uniques::Config>::CollectionId,
+        <T as pallet_uniques::Config>::ItemId,
+        <T as pallet_assets::Config>::AssetId,
+        <T as pallet_assets::Config>::Balance,
+    >;
+
+    #[cfg(all(feature = "rmrk", not(feature = "assets")))]
+    pub type AnyIdOf<T> = AnyId<
+        <T as Config>::IpId,
+        <T as ipf::Config>::IpfId,
+        (CollectionId, NftId),
+        CollectionId,
+        <T as pallet_uniques::Config>::CollectionId,
+        <T as pallet_uniques::Config>::ItemId,
+    >;
+
+    #[cfg(all(feature = "uniques", not(feature = "rmrk"), feature = "assets"))]
+    pub type AnyIdOf<T> = AnyId<
+        <T as Config>::IpId,
+        <T as ipf::Config>::IpfId,
+        <T as pallet_uniques::Config>::CollectionId,
+        <T as pallet_uniques::Config>::ItemId,
+        <T as pallet_assets::Config>::AssetId,
+        <T as pallet_assets::Config>::Balance,
+    >;
+
+    #[cfg(all(feature = "uniques", not(feature = "rmrk"), not(feature = "assets")))]
+    pub type AnyIdOf<T> = AnyId<
+        <T as Config>::IpId,
+        <T as ipf::Config>::IpfId,
+        <T as pallet_uniques::Config>::CollectionId,
+        <T as pallet_uniques::Config>::ItemId,
+    >;
+
+    #[cfg(all(not(feature = "uniques"), feature = "assets"))]
+    pub type AnyIdOf<T> = AnyId<
+        <T as Config>::IpId,
+        <T as ipf::Config>::IpfId,
+        <T as pallet_assets::Config>::AssetId,
+        <T as pallet_assets::Config>::Balance,
+    >;
+
+    #[cfg(not(any(feature = "uniques", feature = "assets")))]
+    pub type AnyIdOf<T> = AnyId<<T as Config>::IpId, <T as ipf::Config>::IpfId>;
+
+    /// Key and value of `FungibleHoldings`, which stays empty without the `assets` feature
+    #[cfg(feature = "assets")]
+    pub type FungibleAssetIdOf<T> = <T as pallet_assets::Config>::AssetId;
+    #[cfg(feature = "assets")]
+    pub type FungibleBalanceOf<T> = <T as pallet_assets::Config>::Balance;
+    #[cfg(not(feature = "assets"))]
+    pub type FungibleAssetIdOf<T> = <T as Config>::IpId;
+    #[cfg(not(feature = "assets"))]
+    pub type FungibleBalanceOf<T> = <T as Config>::Balance;
 
     pub type AnyIdWithNewOwner<T> = (AnyIdOf<T>, <T as frame_system::Config>::AccountId);
 
     #[pallet::pallet]
-    #[pallet::without_storage_info]
+    #[pallet::storage_version(migrations::STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     /// Next available IPS ID.
@@ -163,6 +317,26 @@ pub mod pallet {
     #[pallet::getter(fn ips_storage)]
     pub type IpStorage<T: Config> = StorageMap<_, Blake2_128Concat, T::IpId, IpInfoOf<T>>;
 
+    /// Assets an IP Set directly owns. Fungible assets are tracked in `FungibleHoldings` instead
+    ///
+    /// Key: (IP Set ID, asset)
+    #[pallet::storage]
+    #[pallet::getter(fn ips_assets)]
+    pub type IpsAssets<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::IpId, Blake2_128Concat, AnyIdOf<T>, ()>;
+
+    /// IP Sets an asset is listed in, the reverse of `IpsAssets`
+    ///
+    /// Key: (asset, IP Set ID)
+    #[pallet::storage]
+    pub type AssetIpSets<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, AnyIdOf<T>, Blake2_128Concat, T::IpId, ()>;
+
+    /// Number of entries in `IpsAssets` for each IP Set
+    #[pallet::storage]
+    #[pallet::getter(fn ips_asset_count)]
+    pub type IpsAssetCount<T: Config> = StorageMap<_, Blake2_128Concat, T::IpId, u32, ValueQuery>;
+
     /// IPS existence check by owner and IPS ID
     #[pallet::storage]
     #[pallet::getter(fn ips_by_owner)]
@@ -175,9 +349,29 @@ pub mod pallet {
         (),
     >;
 
+    /// Amount of each `pallet_assets` asset held by an IP Set
+    ///
+    /// Key: (IP Set ID, asset ID)
+    #[pallet::storage]
+    #[pallet::getter(fn fungible_holdings)]
+    pub type FungibleHoldings<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::IpId,
+        Blake2_128Concat,
+        FungibleAssetIdOf<T>,
+        FungibleBalanceOf<T>,
+    >;
+
+    /// Next available multisig operation ID, tracked separately for each IP Set.
+    #[pallet::storage]
+    #[pallet::getter(fn next_multisig_id)]
+    pub type NextMultisigId<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::IpId, crate::ipt::MultisigId, ValueQuery>;
+
     /// Details of a multisig call. Only holds data for calls while they are in the voting stage.
     ///
-    /// Key: (IP Set ID, call hash)
+    /// Key: (IP Set ID, multisig operation ID)
     #[pallet::storage]
     #[pallet::getter(fn multisig)]
     pub type Multisig<T: Config> = StorageDoubleMap<
@@ -185,10 +379,33 @@ pub mod pallet {
         Blake2_128Concat,
         T::IpId,
         Blake2_128Concat,
-        [u8; 32],
+        crate::ipt::MultisigId,
         crate::ipt::MultisigOperationOf<T>,
     >;
 
+    /// Encoded calls of the multisig operations in the voting stage, stored once per call hash.
+    ///
+    /// Key: call hash
+    #[pallet::storage]
+    #[pallet::getter(fn call_preimages)]
+    pub type CallPreimages<T: Config> =
+        StorageMap<_, Identity, [u8; 32], crate::preimage::CallPreimageOf<T>>;
+
+    /// Accounts derived from an IP Set and the caller of a multisig call with `include_caller` set,
+    /// registered the first time a call is dispatched from them.
+    ///
+    /// Key: (IP Set ID, original caller)
+    #[pallet::storage]
+    #[pallet::getter(fn sub_accounts)]
+    pub type SubAccounts<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::IpId,
+        Blake2_128Concat,
+        T::AccountId,
+        T::AccountId,
+    >;
+
     /// Details of a sub token.
     ///
     /// Key: (IP Set ID, sub token ID)
@@ -200,7 +417,7 @@ pub mod pallet {
         T::IpId,
         Blake2_128Concat,
         T::IpId,
-        SubIptInfo<T::IpId, BoundedVec<u8, T::MaxMetadata>>,
+        crate::ipt::SubIptInfoOf<T>,
     >;
 
     /// The holdings of a specific account for a specific token.
@@ -235,6 +452,39 @@ pub mod pallet {
     pub type Permissions<T: Config> =
         StorageDoubleMap<_, Blake2_128Concat, (T::IpId, T::IpId), Blake2_128Concat, [u8; 2], bool>;
 
+    /// Fee paid to an IP Set for each replica made of it, set when replicas are allowed
+    #[pallet::storage]
+    #[pallet::getter(fn replica_fee)]
+    pub type ReplicaFees<T: Config> = StorageMap<_, Blake2_128Concat, T::IpId, ipf::BalanceOf<T>>;
+
+    /// Amount a sub token can transfer out of the IP Set account per period without a vote.
+    ///
+    /// Key: (IP Set ID, sub token ID)
+    #[pallet::storage]
+    #[pallet::getter(fn spend_allowance)]
+    pub type SpendAllowances<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::IpId,
+        Blake2_128Concat,
+        T::IpId,
+        crate::treasury::SpendAllowanceOf<T>,
+    >;
+
+    /// Amount spent by a sub token under its spend allowance and the period it was spent in.
+    ///
+    /// Key: (IP Set ID, sub token ID)
+    #[pallet::storage]
+    #[pallet::getter(fn spent_in_period)]
+    pub type SpentInPeriod<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::IpId,
+        Blake2_128Concat,
+        T::IpId,
+        (T::BlockNumber, <T as pallet::Config>::Balance),
+    >;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(crate) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -263,8 +513,11 @@ pub mod pallet {
             new_metadata: Option<Vec<u8>>,
             assets_and_new_owners: Vec<AnyIdWithNewOwner<T>>,
         },
-        /// Replicas of this IP Set are now allowed
-        AllowedReplica { ips_id: T::IpId },
+        /// Replicas of this IP Set are now allowed, each paying `fee` to the IP Set
+        AllowedReplica {
+            ips_id: T::IpId,
+            fee: ipf::BalanceOf<T>,
+        },
         /// Replicas of this IP Set are no longer allowed
         DisallowedReplica { ips_id: T::IpId },
         /// A replica of this IP Set was created
@@ -288,19 +541,20 @@ pub mod pallet {
         },
         /// A vote to execute a call has begun. The call needs more votes to pass.
         ///
-        /// Params: caller derived account ID, caller weighted balance, IPT0 token supply, the call hash, the `Call`
+        /// Params: caller derived account ID, caller weighted balance, IPT0 token supply, the multisig ID, the call hash, the `Call`
         MultisigVoteStarted {
             ips_id: T::IpId,
             executor_account: T::AccountId,
             voter: T::AccountId,
             votes_added: <T as pallet::Config>::Balance,
             votes_required: <T as pallet::Config>::Balance,
+            multisig_id: crate::ipt::MultisigId,
             call_hash: [u8; 32],
             call: crate::ipt::OpaqueCall<T>,
         },
         /// Voting weight was added towards the vote threshold, but not enough to execute the `Call`
         ///
-        /// Params: caller derived account ID, caller weighted balance, IPT0 token supply, the call hash, the `Call`
+        /// Params: caller derived account ID, caller weighted balance, IPT0 token supply, the multisig ID, the call hash
         MultisigVoteAdded {
             ips_id: T::IpId,
             executor_account: T::AccountId,
@@ -308,8 +562,8 @@ pub mod pallet {
             votes_added: <T as pallet::Config>::Balance,
             current_votes: <T as pallet::Config>::Balance,
             votes_required: <T as pallet::Config>::Balance,
+            multisig_id: crate::ipt::MultisigId,
             call_hash: [u8; 32],
-            call: crate::ipt::OpaqueCall<T>,
         },
         MultisigVoteWithdrawn {
             ips_id: T::IpId,
@@ -317,28 +571,46 @@ pub mod pallet {
             voter: T::AccountId,
             votes_removed: <T as pallet::Config>::Balance,
             votes_required: <T as pallet::Config>::Balance,
+            multisig_id: crate::ipt::MultisigId,
             call_hash: [u8; 32],
-            call: crate::ipt::OpaqueCall<T>,
         },
         /// Multisig call was executed.
         ///
-        /// Params: caller derived account ID, OpaqueCall, dispatch result is ok
+        /// Params: caller derived account ID, multisig ID, OpaqueCall, dispatch result (with the module index and error if it failed)
         MultisigExecuted {
             ips_id: T::IpId,
             executor_account: T::AccountId,
             voter: T::AccountId,
+            multisig_id: crate::ipt::MultisigId,
             call_hash: [u8; 32],
             call: crate::ipt::OpaqueCall<T>,
-            result: bool,
+            result: DispatchResult,
         },
         /// The vote on a multisig call was cancelled/withdrawn
         ///
-        /// Params: caller derived account ID, the call hash
+        /// Params: caller derived account ID, the multisig ID, the call hash
         MultisigCanceled {
             ips_id: T::IpId,
             executor_account: T::AccountId,
+            multisig_id: crate::ipt::MultisigId,
             call_hash: [u8; 32],
         },
+        /// A multisig call was dispatched from an `include_caller` sub account for the first time
+        ///
+        /// Params: IP Set ID, account the sub account is derived from, the sub account
+        SubAccountCreated {
+            ips_id: T::IpId,
+            original_caller: T::AccountId,
+            sub_account: T::AccountId,
+        },
+        /// The balance of a sub account was moved back to the IP Set account and the sub account was closed
+        ///
+        /// Params: IP Set ID, account the sub account is derived from, the sub account
+        SubAccountSwept {
+            ips_id: T::IpId,
+            original_caller: T::AccountId,
+            sub_account: T::AccountId,
+        },
         /// One of more sub tokens were created
         SubTokenCreated {
             sub_tokens_with_endowment: Vec<(
@@ -364,6 +636,23 @@ pub mod pallet {
             sub_token_id: T::IpId,
             voting_weight: OneOrPercent,
         },
+        /// The spend allowance was set (or removed if `None`) for a sub token
+        ///
+        /// Params: IP Set ID, Sub token ID, amount and period of the allowance
+        SpendAllowanceSet {
+            ips_id: T::IpId,
+            sub_token_id: T::IpId,
+            allowance: Option<crate::treasury::SpendAllowanceOf<T>>,
+        },
+        /// A sub token transferred funds out of the IP Set account under its spend allowance
+        ///
+        /// Params: IP Set ID, Sub token ID, amount transferred, total spent in the current period
+        AllowanceSpent {
+            ips_id: T::IpId,
+            sub_token_id: T::IpId,
+            amount: <T as pallet::Config>::Balance,
+            spent_in_period: <T as pallet::Config>::Balance,
+        },
     }
 
     /// Errors for IPF pallet
@@ -400,9 +689,10 @@ pub mod pallet {
         TooManySignatories,
         UnexistentBalance,
         MultisigOperationUninitialized,
+        /// The call stored for a multisig operation doesn't decode anymore, e.g. after a runtime upgrade
         CouldntDecodeCall,
-        /// Multisig operation already exists and is available for voting
-        MultisigOperationAlreadyExists,
+        /// No available multisig operation ID
+        NoAvailableMultisigId,
         /// Cannot withdraw a vote on a multisig transaction you have not voted on
         NotAVoter,
         UnknownError,
@@ -424,14 +714,34 @@ pub mod pallet {
         WasmPermissionsDisabled,
         /// Multisig is not allowed to call these extrinsics
         CantExecuteThisCall,
+        /// The runtime call filter does not allow this call
+        CallFiltered,
 
         InvalidWasmPermission,
         WasmPermissionFailedExecution,
 
-        /// Division by 0 happened somewhere, maybe you have IPT assets with no decimal points?
-        DivisionByZero,
-
         Overflow,
+
+        /// No sub account has been used for this IP Set and caller
+        SubAccountNotFound,
+        /// Spend allowance periods must be at least 1 block long
+        InvalidSpendPeriod,
+        /// The transfer is above what is left of the spend allowance for this period
+        SpendAllowanceExceeded,
+        /// The runtime has no handler for this kind of asset
+        UnsupportedAsset,
+        /// Max amount of assets in an IP Set reached
+        TooManyAssets,
+        /// The encoded call is larger than `MaxCallSize`
+        CallTooLarge,
+        /// The call of a multisig operation is no longer stored
+        CallPreimageNotFound,
+        /// The asset is already part of this IP Set
+        AssetAlreadyInIps,
+        /// IPFs can only leave an IP Set with the price they were sold for, so their royalty can be paid
+        PriceRequired,
+        /// Less than the fee of a multisig call was still reserved from a voter when collecting it
+        MultisigFeeNotReserved,
     }
 
     /// Dispatch functions
@@ -459,6 +769,7 @@ pub mod pallet {
                 ipl_default_asset_weight,
                 ipl_default_permission,
             )
+            .map(|_| ())
         }
 
         // /// Delete an IP Set and all of its contents
@@ -496,7 +807,8 @@ pub mod pallet {
             Pallet::<T>::inner_append(owner, ips_id, original_caller, assets, new_metadata)
         }
 
-        /// Remove assets from an IP Set
+        /// Remove assets from an IP Set.
+        /// IPFs need the `price` they were sold for, their authors get their royalty on it from the IP Set account
         #[pallet::weight(200_000_000)] // TODO: Set correct weight
         pub fn remove(
             owner: OriginFor<T>,
@@ -504,14 +816,19 @@ pub mod pallet {
             original_caller: Option<T::AccountId>,
             assets: Vec<AnyIdWithNewOwner<T>>,
             new_metadata: Option<Vec<u8>>,
+            price: Option<ipf::BalanceOf<T>>,
         ) -> DispatchResult {
-            Pallet::<T>::inner_remove(owner, ips_id, original_caller, assets, new_metadata)
+            Pallet::<T>::inner_remove(owner, ips_id, original_caller, assets, new_metadata, price)
         }
 
-        /// Allows replicas of this IPS to be made.
+        /// Allows replicas of this IPS to be made, each paying `fee` to the IP Set.
         #[pallet::weight(200_000_000)]
-        pub fn allow_replica(owner: OriginFor<T>, ips_id: T::IpId) -> DispatchResult {
-            Pallet::<T>::inner_allow_replica(owner, ips_id)
+        pub fn allow_replica(
+            owner: OriginFor<T>,
+            ips_id: T::IpId,
+            fee: ipf::BalanceOf<T>,
+        ) -> DispatchResult {
+            Pallet::<T>::inner_allow_replica(owner, ips_id, fee)
         }
 
         /// Disallows replicas of this IPS to be made.
@@ -520,24 +837,26 @@ pub mod pallet {
             Pallet::<T>::inner_disallow_replica(owner, ips_id)
         }
 
-        // #[pallet::weight(100_000)]
-        // pub fn create_replica(
-        //     owner: OriginFor<T>,
-        //     original_ips_id: T::IpId,
-        //     ipl_license: <T as Config>::Licenses,
-        //     ipl_execution_threshold: OneOrPercent,
-        //     ipl_default_asset_weight: OneOrPercent,
-        //     ipl_default_permission: bool,
-        // ) -> DispatchResultWithPostInfo {
-        //     Pallet::<T>::inner_create_replica(
-        //         owner,
-        //         original_ips_id,
-        //         ipl_license,
-        //         ipl_execution_threshold,
-        //         ipl_default_asset_weight,
-        //         ipl_default_permission,
-        //     )
-        // }
+        /// Create a replica of an IP Set, licensing its contents for the replica fee.
+        /// The authors of its IPFs get their royalty on the fee.
+        #[pallet::weight(200_000_000)] // TODO: Set correct weight
+        pub fn create_replica(
+            owner: OriginFor<T>,
+            original_ips_id: T::IpId,
+            ipl_license: <T as Config>::Licenses,
+            ipl_execution_threshold: OneOrPercent,
+            ipl_default_asset_weight: OneOrPercent,
+            ipl_default_permission: bool,
+        ) -> DispatchResultWithPostInfo {
+            Pallet::<T>::inner_create_replica(
+                owner,
+                original_ips_id,
+                ipl_license,
+                ipl_execution_threshold,
+                ipl_default_asset_weight,
+                ipl_default_permission,
+            )
+        }
 
         /// Mint `amount` of specified token to `target` account
         #[pallet::weight(200_000_000)] // TODO: Set correct weight
@@ -576,18 +895,18 @@ pub mod pallet {
         pub fn vote_multisig(
             caller: OriginFor<T>,
             ipt_id: (T::IpId, Option<T::IpId>),
-            call_hash: [u8; 32],
+            multisig_id: crate::ipt::MultisigId,
         ) -> DispatchResultWithPostInfo {
-            Pallet::<T>::inner_vote_multisig(caller, ipt_id, call_hash)
+            Pallet::<T>::inner_vote_multisig(caller, ipt_id, multisig_id)
         }
 
         #[pallet::weight(250_000_000)]
         pub fn withdraw_vote_multisig(
             caller: OriginFor<T>,
             ipt_id: (T::IpId, Option<T::IpId>),
-            call_hash: [u8; 32],
+            multisig_id: crate::ipt::MultisigId,
         ) -> DispatchResultWithPostInfo {
-            Pallet::<T>::inner_withdraw_vote_multisig(caller, ipt_id, call_hash)
+            Pallet::<T>::inner_withdraw_vote_multisig(caller, ipt_id, multisig_id)
         }
 
         /// Create one or more sub tokens for an IP Set
@@ -620,8 +939,45 @@ pub mod pallet {
         ) -> DispatchResult {
             Pallet::<T>::inner_set_sub_token_weight(owner, ips_id, sub_token_id, voting_weight)
         }
+
+        /// Move the whole balance of the sub account derived from `ips_id` and `original_caller`
+        /// back to the main IP Set account and close it
+        #[pallet::weight(200_000_000)] // TODO: Set correct weight
+        pub fn sweep_sub_account(
+            caller: OriginFor<T>,
+            ips_id: T::IpId,
+            original_caller: T::AccountId,
+        ) -> DispatchResult {
+            Pallet::<T>::inner_sweep_sub_account(caller, ips_id, original_caller)
+        }
+
+        /// Allow a sub token to transfer up to `allowance.amount` out of the IP Set account every
+        /// `allowance.period` blocks without a vote. `None` removes the allowance.
+        #[pallet::weight(200_000_000)] // TODO: Set correct weight
+        pub fn set_spend_allowance(
+            owner: OriginFor<T>,
+            ips_id: T::IpId,
+            sub_token_id: T::IpId,
+            allowance: Option<crate::treasury::SpendAllowanceOf<T>>,
+        ) -> DispatchResult {
+            Pallet::<T>::inner_set_spend_allowance(owner, ips_id, sub_token_id, allowance)
+        }
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            migrations::migrate::<T>()
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<(), &'static str> {
+            migrations::pre_upgrade::<T>()
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade() -> Result<(), &'static str> {
+            migrations::post_upgrade::<T>()
+        }
+    }
 }
@@ -0,0 +1,435 @@
+use super::pallet::{self, *};
+use frame_support::{
+    pallet_prelude::*,
+    traits::{Currency, ExistenceRequirement, Get, GetStorageVersion, StorageVersion},
+    weights::WeightToFee,
+};
+use sp_arithmetic::traits::{Saturating, Zero};
+
+/// Current storage version of the pallet
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
+/// Bounded storage: IP Set contents move to `IpsAssets` and multisig calls to `CallPreimages`.
+/// IPFs in IP Sets get locked in `pallet-ipf`
+pub mod v1 {
+    use super::*;
+    use crate::{
+        ips::{IpsMetadataOf, IPS_LOCK_ID},
+        ipt::OpaqueCall,
+        util::derive_ips_account,
+    };
+    use primitives::{IpInfo, IpsType, OneOrPercent, Parentage};
+    use sp_std::vec::Vec;
+
+    /// `IpInfo` as stored before v1, holding the IP Set contents in `data`
+    #[derive(Encode, Decode)]
+    pub struct OldIpInfo<AccountId, Data, IpsMetadataOf, IpId, Balance, LicenseMetadata, Hash> {
+        pub parentage: Parentage<AccountId, IpId>,
+        pub metadata: IpsMetadataOf,
+        pub data: Data,
+        pub ips_type: IpsType<IpId>,
+        pub allow_replica: bool,
+        pub supply: Balance,
+        pub license: (LicenseMetadata, Hash),
+        pub execution_threshold: OneOrPercent,
+        pub default_asset_weight: OneOrPercent,
+        pub default_permission: bool,
+    }
+
+    /// `AnyId` as stored before v1. RMRK variants decode whether or not the `rmrk` feature is enabled
+    #[derive(Encode, Decode)]
+    pub enum OldAnyId<IpsId, IpfId> {
+        IpfId(IpfId),
+        RmrkNft((u32, u32)),
+        RmrkCollection(u32),
+        IpsId(IpsId),
+    }
+
+    pub type OldAnyIdOf<T> = OldAnyId<<T as pallet::Config>::IpId, <T as ipf::Config>::IpfId>;
+
+    /// The asset as it's held from v1 on, `None` for RMRK assets when the `rmrk` feature is disabled
+    fn new_asset<T: Config>(old: OldAnyIdOf<T>) -> Option<AnyIdOf<T>> {
+        match old {
+            OldAnyId::IpfId(ipf_id) => Some(AnyId::IpfId(ipf_id)),
+            OldAnyId::IpsId(ips_id) => Some(AnyId::IpsId(ips_id)),
+            #[cfg(feature = "rmrk")]
+            OldAnyId::RmrkNft(nft) => Some(AnyId::RmrkNft(nft)),
+            #[cfg(feature = "rmrk")]
+            OldAnyId::RmrkCollection(collection_id) => Some(AnyId::RmrkCollection(collection_id)),
+            #[cfg(not(feature = "rmrk"))]
+            OldAnyId::RmrkNft(_) | OldAnyId::RmrkCollection(_) => None,
+        }
+    }
+
+    pub type OldIpInfoOf<T> = OldIpInfo<
+        <T as frame_system::Config>::AccountId,
+        Vec<OldAnyIdOf<T>>,
+        IpsMetadataOf<T>,
+        <T as pallet::Config>::IpId,
+        <T as pallet::Config>::Balance,
+        BoundedVec<u8, <T as pallet::Config>::MaxMetadata>,
+        <T as frame_system::Config>::Hash,
+    >;
+
+    /// Multisig operation as stored before v1, keyed by call hash
+    #[derive(Encode, Decode)]
+    pub struct OldMultisigOperation<AccountId, IpId, Call, Metadata> {
+        pub signers: Vec<(AccountId, Option<IpId>)>,
+        pub include_original_caller: bool,
+        pub original_caller: AccountId,
+        pub actual_call: Call,
+        pub call_metadata: [u8; 2],
+        pub call_weight: Weight,
+        pub metadata: Option<Metadata>,
+    }
+
+    pub type OldMultisigOperationOf<T> = OldMultisigOperation<
+        <T as frame_system::Config>::AccountId,
+        <T as pallet::Config>::IpId,
+        OpaqueCall<T>,
+        BoundedVec<u8, <T as pallet::Config>::MaxMetadata>,
+    >;
+
+    /// `Multisig` as stored before v1
+    #[frame_support::storage_alias]
+    pub type Multisig<T: Config> = StorageDoubleMap<
+        Pallet<T>,
+        Blake2_128Concat,
+        <T as pallet::Config>::IpId,
+        Blake2_128Concat,
+        [u8; 32],
+        OldMultisigOperationOf<T>,
+    >;
+
+    /// Votes needed to execute a multisig call of `ips_id`, counted like before v1
+    fn total_per_threshold<T: Config>(
+        ips_id: <T as pallet::Config>::IpId,
+    ) -> Option<<T as pallet::Config>::Balance> {
+        let ips = IpStorage::<T>::get(ips_id)?;
+
+        let mut total_issuance = ips.supply;
+        for sub_token_id in SubAssets::<T>::iter_key_prefix(ips_id) {
+            let supply = Balance::<T>::iter_prefix_values((ips_id, Some(sub_token_id))).fold(
+                Zero::zero(),
+                |acc: <T as pallet::Config>::Balance, balance| acc.saturating_add(balance),
+            );
+
+            total_issuance = total_issuance.saturating_add(
+                match Pallet::<T>::asset_weight(ips_id, sub_token_id)? {
+                    OneOrPercent::ZeroPoint(weight) => weight * supply,
+                    OneOrPercent::One => supply,
+                },
+            );
+        }
+
+        Some(match ips.execution_threshold {
+            OneOrPercent::ZeroPoint(percent) => percent * total_issuance,
+            OneOrPercent::One => total_issuance,
+        })
+    }
+
+    /// Move the contents of every IP Set into `IpsAssets` and drop the pending multisig operations.
+    ///
+    /// Pending operations were keyed by call hash and held the call unbounded, so they can't be carried over,
+    /// votes on them have to be started again. Their signers get back the share of the fee they paid into the
+    /// IP Set account, the same amount cancelling the operation used to refund.
+    /// Runtimes that put RMRK assets in IP Sets must enable the `rmrk` feature before running this, otherwise
+    /// those assets are dropped from the IP Sets.
+    pub fn migrate<T: Config>() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() >= 1 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let mut reads: u64 = 1;
+        let mut writes: u64 = 0;
+        let mut dropped_assets: u32 = 0;
+        let mut failed_locks: u32 = 0;
+
+        IpStorage::<T>::translate::<OldIpInfoOf<T>, _>(|ips_id, old| {
+            reads += 1;
+            writes += 1;
+
+            let mut count: u32 = 0;
+            for asset in old.data {
+                let asset = match new_asset::<T>(asset) {
+                    Some(asset) => asset,
+                    None => {
+                        dropped_assets = dropped_assets.saturating_add(1);
+                        continue;
+                    }
+                };
+
+                reads += 1;
+                if !IpsAssets::<T>::contains_key(ips_id, &asset) {
+                    if let AnyId::IpfId(ipf_id) = asset {
+                        reads += 2;
+                        match ipf::Pallet::<T>::lock(ipf_id, IPS_LOCK_ID) {
+                            Ok(()) => writes += 1,
+                            Err(error) => {
+                                failed_locks = failed_locks.saturating_add(1);
+                                log::warn!(
+                                    target: "runtime::inv4",
+                                    "v1: couldn't lock IPF {:?} of IP Set {:?}: {:?}",
+                                    ipf_id,
+                                    ips_id,
+                                    error,
+                                );
+                            }
+                        }
+                    }
+
+                    AssetIpSets::<T>::insert(&asset, ips_id, ());
+                    IpsAssets::<T>::insert(ips_id, asset, ());
+                    count = count.saturating_add(1);
+                    writes += 2;
+                }
+            }
+
+            IpsAssetCount::<T>::insert(ips_id, count);
+            writes += 1;
+
+            Some(IpInfo {
+                parentage: old.parentage,
+                metadata: old.metadata,
+                ips_type: old.ips_type,
+                allow_replica: old.allow_replica,
+                supply: old.supply,
+                license: old.license,
+                execution_threshold: old.execution_threshold,
+                default_asset_weight: old.default_asset_weight,
+                default_permission: old.default_permission,
+            })
+        });
+
+        if dropped_assets > 0 {
+            log::error!(
+                target: "runtime::inv4",
+                "v1: dropped {} RMRK assets from IP Sets, the `rmrk` feature is disabled",
+                dropped_assets,
+            );
+        }
+
+        if failed_locks > 0 {
+            log::error!(
+                target: "runtime::inv4",
+                "v1: {} IPFs in IP Sets couldn't be locked",
+                failed_locks,
+            );
+        }
+
+        let mut failed_refunds: u32 = 0;
+        for (ips_id, _, operation) in Multisig::<T>::drain() {
+            reads += 1;
+            writes += 1;
+
+            let fee: <T as pallet::Config>::Balance =
+                T::WeightToFee::weight_to_fee(&operation.call_weight).into();
+            let fee_per_vote = total_per_threshold::<T>(ips_id)
+                .and_then(|total| fee.checked_div(&total))
+                .unwrap_or_else(Zero::zero);
+            let ips_account = derive_ips_account::<T>(ips_id, None);
+
+            for (signer, token) in operation.signers {
+                reads += 1;
+
+                let refund = fee_per_vote.saturating_mul(
+                    Balance::<T>::get((ips_id, token), &signer).unwrap_or_default(),
+                );
+                if refund.is_zero() {
+                    continue;
+                }
+
+                match <pallet_balances::Pallet<T> as Currency<T::AccountId>>::transfer(
+                    &ips_account,
+                    &signer,
+                    refund.into(),
+                    ExistenceRequirement::AllowDeath,
+                ) {
+                    Ok(()) => writes += 2,
+                    Err(error) => {
+                        failed_refunds = failed_refunds.saturating_add(1);
+                        log::warn!(
+                            target: "runtime::inv4",
+                            "v1: couldn't refund {:?} of a multisig fee from IP Set {:?} to {:?}: {:?}",
+                            refund,
+                            ips_id,
+                            signer,
+                            error,
+                        );
+                    }
+                }
+            }
+        }
+
+        if failed_refunds > 0 {
+            log::error!(
+                target: "runtime::inv4",
+                "v1: {} multisig fee refunds failed",
+                failed_refunds,
+            );
+        }
+
+        StorageVersion::new(1).put::<Pallet<T>>();
+        writes += 1;
+
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+
+    /// Every IP Set must decode in its old shape, and hold no RMRK assets unless the `rmrk` feature is enabled
+    #[cfg(feature = "try-runtime")]
+    pub fn pre_upgrade<T: Config>() -> Result<(), &'static str> {
+        let mut undecodable: u32 = 0;
+        let mut rmrk_assets: u32 = 0;
+
+        for ips_id in IpStorage::<T>::iter_keys() {
+            let old =
+                frame_support::storage::unhashed::get_raw(&IpStorage::<T>::hashed_key_for(ips_id))
+                    .and_then(|raw| OldIpInfoOf::<T>::decode(&mut &raw[..]).ok());
+
+            match old {
+                Some(old) => {
+                    let held = old.data.len();
+                    let kept = old.data.into_iter().filter_map(new_asset::<T>).count();
+                    rmrk_assets = rmrk_assets.saturating_add((held - kept) as u32);
+                }
+                None => {
+                    undecodable = undecodable.saturating_add(1);
+                    log::error!(target: "runtime::inv4", "v1: IP Set {:?} doesn't decode", ips_id);
+                }
+            }
+        }
+
+        if undecodable > 0 {
+            return Err("Some IP Sets don't decode in their pre v1 shape");
+        }
+
+        if rmrk_assets > 0 {
+            log::error!(
+                target: "runtime::inv4",
+                "v1: {} RMRK assets would be dropped from IP Sets",
+                rmrk_assets,
+            );
+            return Err("IP Sets hold RMRK assets but the `rmrk` feature is disabled");
+        }
+
+        Ok(())
+    }
+
+    /// Every IP Set's asset count must match its entries in `IpsAssets`
+    #[cfg(feature = "try-runtime")]
+    pub fn post_upgrade<T: Config>() -> Result<(), &'static str> {
+        for (ips_id, count) in IpsAssetCount::<T>::iter() {
+            if IpsAssets::<T>::iter_prefix(ips_id).count() as u32 != count {
+                return Err("IpsAssetCount doesn't match the IP Set's assets");
+            }
+        }
+
+        for (ips_id, asset, ()) in IpsAssets::<T>::iter() {
+            if !AssetIpSets::<T>::contains_key(asset, ips_id) {
+                return Err("IpsAssets entry missing from AssetIpSets");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Sub tokens keep track of their own supply, like IPT0 does in `IpInfo`
+pub mod v2 {
+    use super::*;
+    use crate::ipt::SubIptInfoOf;
+    use primitives::SubIptInfo;
+
+    /// `SubIptInfo` as stored before v2, without a supply
+    #[derive(Encode, Decode)]
+    pub struct OldSubIptInfo<IptId, SubAssetMetadata> {
+        pub id: IptId,
+        pub metadata: SubAssetMetadata,
+    }
+
+    pub type OldSubIptInfoOf<T> = OldSubIptInfo<
+        <T as pallet::Config>::IpId,
+        BoundedVec<u8, <T as pallet::Config>::MaxMetadata>,
+    >;
+
+    /// Set the supply of every sub token to the sum of its holders' balances.
+    pub fn migrate<T: Config>() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() >= 2 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let mut reads: u64 = 1;
+        let mut writes: u64 = 0;
+
+        SubAssets::<T>::translate::<OldSubIptInfoOf<T>, _>(|ips_id, sub_token_id, old| {
+            reads += 1;
+            writes += 1;
+
+            let mut supply: <T as pallet::Config>::Balance = Zero::zero();
+            for balance in Balance::<T>::iter_prefix_values((ips_id, Some(sub_token_id))) {
+                reads += 1;
+                supply = supply.saturating_add(balance);
+            }
+
+            let new: SubIptInfoOf<T> = SubIptInfo {
+                id: old.id,
+                metadata: old.metadata,
+                supply,
+            };
+
+            Some(new)
+        });
+
+        StorageVersion::new(2).put::<Pallet<T>>();
+        writes += 1;
+
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+
+    /// Every sub token's supply must match the sum of its holders' balances
+    #[cfg(feature = "try-runtime")]
+    pub fn post_upgrade<T: Config>() -> Result<(), &'static str> {
+        for (ips_id, sub_token_id, sub) in SubAssets::<T>::iter() {
+            let supply = Balance::<T>::iter_prefix_values((ips_id, Some(sub_token_id))).fold(
+                Zero::zero(),
+                |acc: <T as pallet::Config>::Balance, balance| acc.saturating_add(balance),
+            );
+
+            if sub.supply != supply {
+                return Err("Sub token supply doesn't match its balances");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Run every migration the on-chain storage version hasn't gone through yet
+pub fn migrate<T: Config>() -> Weight {
+    v1::migrate::<T>().saturating_add(v2::migrate::<T>())
+}
+
+/// The migrations only run from an older storage version
+#[cfg(feature = "try-runtime")]
+pub fn pre_upgrade<T: Config>() -> Result<(), &'static str> {
+    if Pallet::<T>::on_chain_storage_version() > STORAGE_VERSION {
+        return Err("On-chain storage version is newer than the pallet's");
+    }
+
+    if Pallet::<T>::on_chain_storage_version() < 1 {
+        v1::pre_upgrade::<T>()?;
+    }
+
+    Ok(())
+}
+
+/// Storage must be at the current version and in the shape the migrations leave it in
+#[cfg(feature = "try-runtime")]
+pub fn post_upgrade<T: Config>() -> Result<(), &'static str> {
+    if Pallet::<T>::on_chain_storage_version() != STORAGE_VERSION {
+        return Err("On-chain storage version wasn't updated");
+    }
+
+    v1::post_upgrade::<T>()?;
+    v2::post_upgrade::<T>()
+}
@@ -0,0 +1,318 @@
+//! Mocks for the INV4 pallet.
+
+use frame_support::{
+    construct_runtime, parameter_types,
+    traits::{AsEnsureOriginWithArg, ConstU128, ConstU32, Contains},
+    weights::{Weight, WeightToFee},
+};
+use frame_system::EnsureRoot;
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    Percent,
+};
+use sp_std::convert::TryInto;
+
+use super::*;
+use crate as inv4;
+#[cfg(feature = "uniques")]
+use crate::asset_handler::UniquesHandler;
+use crate::{
+    asset_handler::IpfHandler,
+    fee::{IpsPaysFee, MultisigFeeHandler, ProportionalFee, ProposerPaysFee},
+    ipl::LicenseList,
+};
+
+pub type AccountId = u128;
+pub type Balance = u128;
+pub type BlockNumber = u64;
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+}
+
+/// Filters `remark_with_event`, so the tests have a call the runtime doesn't allow
+pub struct BaseFilter;
+
+impl Contains<Call> for BaseFilter {
+    fn contains(call: &Call) -> bool {
+        !matches!(
+            call,
+            Call::System(frame_system::Call::remark_with_event { .. })
+        )
+    }
+}
+
+impl frame_system::Config for Runtime {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Call = Call;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type DbWeight = ();
+    type BaseCallFilter = BaseFilter;
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: Balance = 1;
+}
+
+impl pallet_balances::Config for Runtime {
+    type MaxLocks = ();
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxReserves = ConstU32<50>;
+    type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_uniques::Config for Runtime {
+    type Event = Event;
+    type CollectionId = u32;
+    type ItemId = u32;
+    type Currency = Balances;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type CreateOrigin = AsEnsureOriginWithArg<frame_system::EnsureSigned<AccountId>>;
+    type Locker = ();
+    type CollectionDeposit = ConstU128<0>;
+    type ItemDeposit = ConstU128<0>;
+    type MetadataDepositBase = ConstU128<0>;
+    type AttributeDepositBase = ConstU128<0>;
+    type DepositPerByte = ConstU128<0>;
+    type StringLimit = ConstU32<32>;
+    type KeyLimit = ConstU32<32>;
+    type ValueLimit = ConstU32<32>;
+    type WeightInfo = ();
+}
+
+impl pallet_assets::Config for Runtime {
+    type Event = Event;
+    type Balance = Balance;
+    type AssetId = u32;
+    type Currency = Balances;
+    type ForceOrigin = EnsureRoot<AccountId>;
+    type AssetDeposit = ConstU128<0>;
+    type AssetAccountDeposit = ConstU128<0>;
+    type MetadataDepositBase = ConstU128<0>;
+    type MetadataDepositPerByte = ConstU128<0>;
+    type ApprovalDeposit = ConstU128<0>;
+    type StringLimit = ConstU32<32>;
+    type Freezer = ();
+    type Extra = ();
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const MaxRoyalty: Percent = Percent::from_percent(50);
+    pub const OnDuplicateContent: ipf::DuplicateContent = ipf::DuplicateContent::Derivative;
+}
+
+impl ipf::Config for Runtime {
+    type IpfId = u64;
+    type MaxIpfMetadata = ConstU32<32>;
+    type MaxIpfVersions = ConstU32<2>;
+    type OnDuplicateContent = OnDuplicateContent;
+    type Currency = Balances;
+    type MetadataDepositBase = ConstU128<0>;
+    type MetadataDepositPerByte = ConstU128<0>;
+    type MaxRoyalty = MaxRoyalty;
+    type MaxCoAuthors = ConstU32<2>;
+    type MaxIpfsPerBatch = ConstU32<3>;
+    type IpsCreator = INV4;
+    type Event = Event;
+}
+
+/// License of the IP Sets created in tests
+#[derive(Debug, Clone, Encode, Decode, TypeInfo, Eq, PartialEq)]
+pub enum Licenses {
+    Custom(BoundedVec<u8, <Runtime as inv4::Config>::MaxMetadata>, H256),
+}
+
+impl LicenseList<Runtime> for Licenses {
+    fn get_hash_and_metadata(
+        &self,
+    ) -> (
+        BoundedVec<u8, <Runtime as inv4::Config>::MaxMetadata>,
+        <Runtime as frame_system::Config>::Hash,
+    ) {
+        match self {
+            Licenses::Custom(metadata, hash) => (metadata.clone(), *hash),
+        }
+    }
+}
+
+/// Fee handlers the tests can switch between
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FeeModel {
+    IpsPays,
+    ProposerPays,
+    Proportional,
+}
+
+parameter_types! {
+    pub static MultisigFee: Balance = 100;
+    pub static MultisigFeeModel: FeeModel = FeeModel::Proportional;
+}
+
+/// Every call costs `MultisigFee`, whatever its weight
+pub struct FixedFee;
+
+impl WeightToFee for FixedFee {
+    type Balance = Balance;
+
+    fn weight_to_fee(_weight: &Weight) -> Balance {
+        MultisigFee::get()
+    }
+}
+
+/// Forwards to the fee handler picked by `MultisigFeeModel`
+pub struct MockFeeHandler;
+
+impl MultisigFeeHandler<Runtime> for MockFeeHandler {
+    fn vote_fee(
+        fee: Balance,
+        votes: Balance,
+        votes_required: Balance,
+        is_proposer: bool,
+    ) -> Balance {
+        match MultisigFeeModel::get() {
+            FeeModel::IpsPays => {
+                IpsPaysFee::<Runtime>::vote_fee(fee, votes, votes_required, is_proposer)
+            }
+            FeeModel::ProposerPays => {
+                ProposerPaysFee::<Runtime>::vote_fee(fee, votes, votes_required, is_proposer)
+            }
+            FeeModel::Proportional => {
+                ProportionalFee::<Runtime>::vote_fee(fee, votes, votes_required, is_proposer)
+            }
+        }
+    }
+
+    fn execution_fee(fee: Balance, already_paid: Balance) -> Balance {
+        match MultisigFeeModel::get() {
+            FeeModel::IpsPays => IpsPaysFee::<Runtime>::execution_fee(fee, already_paid),
+            FeeModel::ProposerPays => ProposerPaysFee::<Runtime>::execution_fee(fee, already_paid),
+            FeeModel::Proportional => ProportionalFee::<Runtime>::execution_fee(fee, already_paid),
+        }
+    }
+
+    fn ips_fee(fee: Balance) -> Balance {
+        match MultisigFeeModel::get() {
+            FeeModel::IpsPays => IpsPaysFee::<Runtime>::ips_fee(fee),
+            FeeModel::ProposerPays => ProposerPaysFee::<Runtime>::ips_fee(fee),
+            FeeModel::Proportional => ProportionalFee::<Runtime>::ips_fee(fee),
+        }
+    }
+}
+
+parameter_types! {
+    pub const MaxMetadata: u32 = 32;
+}
+
+pub const PREIMAGE_BASE_DEPOSIT: Balance = 10;
+pub const PREIMAGE_BYTE_DEPOSIT: Balance = 1;
+
+impl inv4::Config for Runtime {
+    type Event = Event;
+    type IpId = u32;
+    type Currency = Balances;
+    type Balance = Balance;
+    type ExistentialDeposit = ExistentialDeposit;
+    type Licenses = Licenses;
+    type Call = Call;
+    type WeightToFee = FixedFee;
+    type FeeHandler = MockFeeHandler;
+    type IpfAssets = IpfHandler<Runtime>;
+    #[cfg(feature = "rmrk")]
+    type RmrkNftAssets = ();
+    #[cfg(feature = "rmrk")]
+    type RmrkCollectionAssets = ();
+    #[cfg(feature = "uniques")]
+    type UniquesAssets = UniquesHandler<Runtime>;
+    type MaxCallers = ConstU32<16>;
+    type MaxCallSize = ConstU32<1024>;
+    type PreimageBaseDeposit = ConstU128<PREIMAGE_BASE_DEPOSIT>;
+    type PreimageByteDeposit = ConstU128<PREIMAGE_BYTE_DEPOSIT>;
+    type MaxSubAssets = ConstU32<16>;
+    type MaxMetadata = MaxMetadata;
+    type MaxAssetsPerIps = ConstU32<3>;
+}
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+type Block = frame_system::mocking::MockBlock<Runtime>;
+
+construct_runtime!(
+    pub enum Runtime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Storage, Config, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Uniques: pallet_uniques::{Pallet, Call, Storage, Event<T>},
+        Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
+        Ipf: ipf::{Pallet, Storage, Event<T>},
+        INV4: inv4::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+pub const ALICE: AccountId = 1;
+pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
+
+pub const MOCK_DATA: [u8; 34] = [
+    18, 32, 12, 47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25,
+    12, 218, 0, 230, 247, 32, 73, 152, 66, 243, 27, 92, 95,
+];
+pub const MOCK_METADATA: &[u8] = b"IP Set";
+
+pub fn mock_license() -> Licenses {
+    Licenses::Custom(b"license".to_vec().try_into().unwrap(), H256::zero())
+}
+
+pub struct ExtBuilder;
+
+impl Default for ExtBuilder {
+    fn default() -> Self {
+        ExtBuilder
+    }
+}
+
+impl ExtBuilder {
+    pub fn build(self) -> sp_io::TestExternalities {
+        let mut t = frame_system::GenesisConfig::default()
+            .build_storage::<Runtime>()
+            .unwrap();
+
+        pallet_balances::GenesisConfig::<Runtime> {
+            balances: vec![(ALICE, 1_000_000), (BOB, 1_000_000), (CHARLIE, 1_000_000)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        let mut ext = sp_io::TestExternalities::new(t);
+        ext.execute_with(|| System::set_block_number(1));
+        ext
+    }
+}
@@ -0,0 +1,96 @@
+use super::pallet::{self, *};
+use crate::ipt::BoundedCallBytes;
+use frame_support::{pallet_prelude::*, traits::ReservableCurrency};
+use sp_arithmetic::traits::{Saturating, Zero};
+use sp_std::{convert::TryInto, vec::Vec};
+
+/// Encoded call of one or more multisig operations, stored once per call hash
+#[derive(Clone, Encode, Decode, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct CallPreimage<AccountId, Balance, Call> {
+    /// The encoded call
+    pub call: Call,
+    /// Account that first noted the call and paid the deposit
+    pub depositor: AccountId,
+    /// Deposit reserved from `depositor` for the stored bytes
+    pub deposit: Balance,
+    /// Number of multisig operations referencing the call
+    pub references: u32,
+}
+
+pub type CallPreimageOf<T> = CallPreimage<
+    <T as frame_system::Config>::AccountId,
+    <T as pallet::Config>::Balance,
+    BoundedCallBytes<T>,
+>;
+
+impl<T: Config> Pallet<T> {
+    /// Store the encoded `call` under `call_hash`, or add a reference to it if it's already stored.
+    /// `depositor` reserves a deposit for the bytes only if they are new.
+    pub(crate) fn note_call_preimage(
+        depositor: &T::AccountId,
+        call_hash: [u8; 32],
+        call: Vec<u8>,
+    ) -> DispatchResult {
+        CallPreimages::<T>::try_mutate(call_hash, |maybe_preimage| -> DispatchResult {
+            if let Some(preimage) = maybe_preimage {
+                preimage.references = preimage
+                    .references
+                    .checked_add(1)
+                    .ok_or(Error::<T>::Overflow)?;
+
+                return Ok(());
+            }
+
+            let call: BoundedCallBytes<T> =
+                call.try_into().map_err(|_| Error::<T>::CallTooLarge)?;
+
+            let deposit = T::PreimageBaseDeposit::get().saturating_add(
+                T::PreimageByteDeposit::get().saturating_mul((call.len() as u128).into()),
+            );
+
+            <pallet_balances::Pallet<T> as ReservableCurrency<T::AccountId>>::reserve(
+                depositor,
+                deposit.into(),
+            )?;
+
+            *maybe_preimage = Some(CallPreimage {
+                call,
+                depositor: depositor.clone(),
+                deposit,
+                references: 1,
+            });
+
+            Ok(())
+        })
+    }
+
+    /// Decode the call stored under `call_hash`
+    pub(crate) fn call_preimage(
+        call_hash: [u8; 32],
+    ) -> Result<<T as pallet::Config>::Call, DispatchError> {
+        let preimage =
+            CallPreimages::<T>::get(call_hash).ok_or(Error::<T>::CallPreimageNotFound)?;
+
+        <T as pallet::Config>::Call::decode(&mut &preimage.call[..])
+            .map_err(|_| Error::<T>::CouldntDecodeCall.into())
+    }
+
+    /// Drop a reference to the call stored under `call_hash`.
+    /// Once no multisig operation references it, the call is removed and the deposit returned.
+    pub(crate) fn unnote_call_preimage(call_hash: [u8; 32]) {
+        CallPreimages::<T>::mutate_exists(call_hash, |maybe_preimage| {
+            if let Some(preimage) = maybe_preimage {
+                preimage.references = preimage.references.saturating_sub(1);
+
+                if preimage.references.is_zero() {
+                    <pallet_balances::Pallet<T> as ReservableCurrency<T::AccountId>>::unreserve(
+                        &preimage.depositor,
+                        preimage.deposit.into(),
+                    );
+
+                    *maybe_preimage = None;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,1891 @@
+//! Unit tests for the INV4 pallet.
+
+use super::*;
+use crate::{
+    asset_handler::IpsAssetHandler, fee::MULTISIG_FEE_RESERVE_ID, ips::IPS_LOCK_ID,
+    util::derive_ips_account,
+};
+use frame_support::{
+    assert_noop, assert_ok,
+    storage::unhashed,
+    traits::{
+        GetStorageVersion, NamedReservableCurrency, ReservableCurrency, StorageVersion,
+        WrapperKeepOpaque,
+    },
+};
+use ipl::LicenseList;
+use migrations::{v1, v2};
+use mock::*;
+use primitives::{IpsType, OneOrPercent, Parentage};
+use sp_io::hashing::blake2_256;
+use sp_runtime::Percent;
+use treasury::SpendAllowance;
+
+/// Create IP Set `ips_id` owned by ALICE, holding `assets`, and return its account
+fn create_ips(ips_id: u32, assets: Vec<AnyIdOf<Runtime>>) -> AccountId {
+    assert_eq!(INV4::next_ips_id(), ips_id);
+    assert_ok!(INV4::create_ips(
+        Origin::signed(ALICE),
+        MOCK_METADATA.to_vec(),
+        assets,
+        false,
+        mock_license(),
+        OneOrPercent::One,
+        OneOrPercent::One,
+        true
+    ));
+
+    derive_ips_account::<Runtime>(ips_id, None)
+}
+
+/// Mint an IPF to `owner` and return its ID
+fn mint_ipf(owner: AccountId) -> u64 {
+    let ipf_id = Ipf::next_ipf_id();
+    assert_ok!(Ipf::mint(
+        Origin::signed(owner),
+        MOCK_METADATA.to_vec(),
+        MOCK_DATA.to_vec()
+    ));
+
+    ipf_id
+}
+
+fn ipf_owner(ipf_id: u64) -> AccountId {
+    Ipf::ipf_storage(ipf_id).unwrap().owner
+}
+
+fn remark(remark: &[u8]) -> Box<mock::Call> {
+    Box::new(mock::Call::System(frame_system::Call::remark {
+        remark: remark.to_vec(),
+    }))
+}
+
+fn transfer(dest: AccountId, value: mock::Balance) -> Box<mock::Call> {
+    Box::new(mock::Call::Balances(pallet_balances::Call::transfer {
+        dest,
+        value,
+    }))
+}
+
+/// Deposit reserved for keeping `call` in `CallPreimages`
+fn preimage_deposit(call: &mock::Call) -> mock::Balance {
+    PREIMAGE_BASE_DEPOSIT + PREIMAGE_BYTE_DEPOSIT * call.encoded_size() as mock::Balance
+}
+
+fn last_event() -> mock::Event {
+    System::events()
+        .pop()
+        .expect("an event was deposited")
+        .event
+}
+
+#[test]
+fn operate_multisig_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+
+        // ALICE holds every IPT0, so the call is executed right away
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"alone")
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigExecuted {
+                multisig_id: 0,
+                result: Ok(()),
+                ..
+            })
+        ));
+        assert!(!Multisig::<Runtime>::contains_key(0, 0));
+
+        // With BOB holding half of the IPT0 a vote starts
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"together")
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigVoteStarted {
+                multisig_id: 1,
+                votes_added: 1_000_000,
+                votes_required: 2_000_000,
+                ..
+            })
+        ));
+        assert!(Multisig::<Runtime>::contains_key(0, 1));
+
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 1));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigExecuted {
+                voter: BOB,
+                multisig_id: 1,
+                result: Ok(()),
+                ..
+            })
+        ));
+        assert!(!Multisig::<Runtime>::contains_key(0, 1));
+    });
+}
+
+#[test]
+fn operate_multisig_same_call_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+
+        // The same call can be proposed again while the first vote is ongoing
+        for _ in 0..2 {
+            assert_ok!(INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                remark(b"twice")
+            ));
+        }
+        assert_eq!(INV4::next_multisig_id(0), 2);
+        assert!(Multisig::<Runtime>::contains_key(0, 0));
+        assert!(Multisig::<Runtime>::contains_key(0, 1));
+
+        // Each operation is voted on separately
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 1));
+        assert!(Multisig::<Runtime>::contains_key(0, 0));
+        assert!(!Multisig::<Runtime>::contains_key(0, 1));
+
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 0));
+        assert!(!Multisig::<Runtime>::contains_key(0, 0));
+    });
+}
+
+#[test]
+fn operate_multisig_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        create_ips(0, vec![]);
+
+        // IP Set doesn't exist
+        assert_noop!(
+            INV4::operate_multisig(Origin::signed(ALICE), false, (1, None), None, remark(b"")),
+            Error::<Runtime>::IpDoesntExist
+        );
+
+        // Sub token doesn't exist
+        assert_noop!(
+            INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, Some(1)),
+                None,
+                remark(b"")
+            ),
+            Error::<Runtime>::SubAssetNotFound
+        );
+
+        // CHARLIE holds no IPT0
+        assert_noop!(
+            INV4::operate_multisig(Origin::signed(CHARLIE), false, (0, None), None, remark(b"")),
+            Error::<Runtime>::NoPermission
+        );
+    });
+}
+
+#[test]
+fn filtered_call_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        create_ips(0, vec![]);
+
+        // The runtime filter would reject the call once dispatched from the IP Set account
+        assert_noop!(
+            INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                Box::new(mock::Call::System(frame_system::Call::remark_with_event {
+                    remark: b"filtered".to_vec(),
+                }))
+            ),
+            Error::<Runtime>::CallFiltered
+        );
+    });
+}
+
+#[test]
+fn blocked_call_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+
+        // Items held by the IP Set can only leave it through `remove`
+        assert_noop!(
+            INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                Box::new(mock::Call::Uniques(pallet_uniques::Call::transfer {
+                    collection: 0,
+                    item: 0,
+                    dest: BOB,
+                }))
+            ),
+            Error::<Runtime>::CantExecuteThisCall
+        );
+
+        // Same for anything moving or burning fungible holdings
+        for call in [
+            pallet_assets::Call::burn {
+                id: 0,
+                who: ips_account,
+                amount: 10,
+            },
+            pallet_assets::Call::transfer_approved {
+                id: 0,
+                owner: ips_account,
+                destination: BOB,
+                amount: 10,
+            },
+            pallet_assets::Call::refund {
+                id: 0,
+                allow_burn: true,
+            },
+        ] {
+            assert_noop!(
+                INV4::operate_multisig(
+                    Origin::signed(ALICE),
+                    false,
+                    (0, None),
+                    None,
+                    Box::new(mock::Call::Assets(call))
+                ),
+                Error::<Runtime>::CantExecuteThisCall
+            );
+        }
+    });
+}
+
+#[test]
+fn sub_token_permission_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::create_sub_token(
+            Origin::signed(ips_account),
+            0,
+            vec![((1, b"sub token".to_vec().try_into().unwrap()), (BOB, 100))]
+        ));
+
+        let call = remark(b"no permission");
+        assert_ok!(INV4::set_permission(
+            Origin::signed(ips_account),
+            0,
+            1,
+            INV4::call_metadata(&call).unwrap(),
+            false
+        ));
+
+        // BOB's sub token may not start the call
+        assert_noop!(
+            INV4::operate_multisig(Origin::signed(BOB), false, (0, Some(1)), None, call),
+            Error::<Runtime>::SubAssetHasNoPermission
+        );
+    });
+}
+
+#[test]
+fn vote_multisig_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"")
+        ));
+
+        // No such multisig operation
+        assert_noop!(
+            INV4::vote_multisig(Origin::signed(BOB), (0, None), 1),
+            Error::<Runtime>::MultisigOperationUninitialized
+        );
+
+        // CHARLIE holds no IPT0
+        assert_noop!(
+            INV4::vote_multisig(Origin::signed(CHARLIE), (0, None), 0),
+            Error::<Runtime>::NoPermission
+        );
+    });
+}
+
+#[test]
+fn withdraw_vote_multisig_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"")
+        ));
+
+        // No such multisig operation
+        assert_noop!(
+            INV4::withdraw_vote_multisig(Origin::signed(ALICE), (0, None), 1),
+            Error::<Runtime>::MultisigOperationUninitialized
+        );
+
+        // BOB didn't vote
+        assert_noop!(
+            INV4::withdraw_vote_multisig(Origin::signed(BOB), (0, None), 0),
+            Error::<Runtime>::NotAVoter
+        );
+    });
+}
+
+#[test]
+fn multisig_fee_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+
+        let alice_balance = Balances::free_balance(ALICE);
+        let bob_balance = Balances::free_balance(BOB);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        // ALICE has half the votes required, so half the fee is reserved from her
+        let call = remark(b"fee");
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            call.clone()
+        ));
+        assert_eq!(
+            Balances::reserved_balance(ALICE),
+            50 + preimage_deposit(&call)
+        );
+
+        // BOB executes the call and pays the rest, both fees go to the IP Set account
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 0));
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert_eq!(Balances::free_balance(ALICE), alice_balance - 50);
+        assert_eq!(Balances::free_balance(BOB), bob_balance - 50);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance + 100);
+    });
+}
+
+#[test]
+fn proposer_pays_fee_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        MultisigFeeModel::set(FeeModel::ProposerPays);
+
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+
+        let alice_balance = Balances::free_balance(ALICE);
+        let bob_balance = Balances::free_balance(BOB);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        let call = remark(b"fee");
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            call.clone()
+        ));
+        assert_eq!(
+            Balances::reserved_balance(ALICE),
+            100 + preimage_deposit(&call)
+        );
+
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 0));
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(ALICE), alice_balance - 100);
+        assert_eq!(Balances::free_balance(BOB), bob_balance);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance + 100);
+    });
+}
+
+#[test]
+fn ips_pays_fee_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        MultisigFeeModel::set(FeeModel::IpsPays);
+
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(Balances::transfer(Origin::signed(BOB), ips_account, 1_000));
+
+        let alice_balance = Balances::free_balance(ALICE);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        // The fee is burned from the IP Set account
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"fee")
+        ));
+        assert_eq!(Balances::free_balance(ALICE), alice_balance);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance - 100);
+    });
+}
+
+#[test]
+fn multisig_fee_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        create_ips(0, vec![]);
+
+        // ALICE can't afford the fee
+        MultisigFee::set(10_000_000);
+        assert_noop!(
+            INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                remark(b"fee")
+            ),
+            pallet_balances::Error::<Runtime>::InsufficientBalance
+        );
+
+        // The IP Set account only holds the existential deposit
+        MultisigFee::set(100);
+        MultisigFeeModel::set(FeeModel::IpsPays);
+        assert_noop!(
+            INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                remark(b"fee")
+            ),
+            pallet_balances::Error::<Runtime>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn multisig_fee_refund_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+
+        let alice_balance = Balances::free_balance(ALICE);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        // The IP Set account can't afford the transfer, ALICE gets the fee back
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            transfer(CHARLIE, 10_000_000)
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigExecuted { result: Err(_), .. })
+        ));
+        assert_eq!(Balances::free_balance(ALICE), alice_balance);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance);
+
+        // Same when the call fails after a vote, every voter gets their fee back
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+        let bob_balance = Balances::free_balance(BOB);
+
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            transfer(CHARLIE, 10_000_000)
+        ));
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 1));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigExecuted { result: Err(_), .. })
+        ));
+        assert_eq!(Balances::free_balance(ALICE), alice_balance);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::free_balance(BOB), bob_balance);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance);
+    });
+}
+
+#[test]
+fn multisig_fee_reserve_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+
+        // ALICE already has funds reserved by something else
+        assert_ok!(Balances::reserve(&ALICE, 500));
+
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"reserve")
+        ));
+        assert_eq!(
+            Balances::reserved_balance_named(&MULTISIG_FEE_RESERVE_ID, &ALICE),
+            50
+        );
+
+        // Canceling only returns what the multisig reserved
+        assert_ok!(INV4::withdraw_vote_multisig(
+            Origin::signed(ALICE),
+            (0, None),
+            0
+        ));
+        assert_eq!(
+            Balances::reserved_balance_named(&MULTISIG_FEE_RESERVE_ID, &ALICE),
+            0
+        );
+        assert_eq!(Balances::reserved_balance(ALICE), 500);
+    });
+}
+
+#[test]
+fn withdraw_vote_multisig_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        for account in [BOB, CHARLIE] {
+            assert_ok!(INV4::ipt_mint(
+                Origin::signed(ips_account),
+                (0, None),
+                1_000_000,
+                account
+            ));
+        }
+
+        let alice_balance = Balances::free_balance(ALICE);
+        let bob_balance = Balances::free_balance(BOB);
+
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            remark(b"withdraw")
+        ));
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 0));
+        assert_eq!(Balances::reserved_balance(BOB), 33);
+
+        // BOB gets the fee back when withdrawing the vote
+        assert_ok!(INV4::withdraw_vote_multisig(
+            Origin::signed(BOB),
+            (0, None),
+            0
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigVoteWithdrawn {
+                voter: BOB,
+                multisig_id: 0,
+                ..
+            })
+        ));
+        assert_eq!(Balances::free_balance(BOB), bob_balance);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert!(Multisig::<Runtime>::contains_key(0, 0));
+
+        // ALICE proposed the call, so withdrawing cancels it and refunds the fee
+        assert_ok!(INV4::withdraw_vote_multisig(
+            Origin::signed(ALICE),
+            (0, None),
+            0
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigCanceled { multisig_id: 0, .. })
+        ));
+        assert_eq!(Balances::free_balance(ALICE), alice_balance);
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert!(!Multisig::<Runtime>::contains_key(0, 0));
+    });
+}
+
+#[test]
+fn sweep_sub_account_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        let sub_account = derive_ips_account::<Runtime>(0, Some(&ALICE));
+
+        // The sub account is registered by the first call dispatched from it
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            true,
+            (0, None),
+            None,
+            remark(b"sub account")
+        ));
+        System::assert_has_event(mock::Event::INV4(crate::Event::SubAccountCreated {
+            ips_id: 0,
+            original_caller: ALICE,
+            sub_account,
+        }));
+        assert_eq!(INV4::sub_accounts(0, ALICE), Some(sub_account));
+
+        // ALICE sweeps it back into the IP Set account
+        assert_ok!(Balances::transfer(Origin::signed(BOB), sub_account, 500));
+        let ips_balance = Balances::free_balance(ips_account);
+
+        assert_ok!(INV4::sweep_sub_account(Origin::signed(ALICE), 0, ALICE));
+        System::assert_last_event(mock::Event::INV4(crate::Event::SubAccountSwept {
+            ips_id: 0,
+            original_caller: ALICE,
+            sub_account,
+        }));
+        assert_eq!(Balances::free_balance(sub_account), 0);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance + 500);
+        assert_eq!(INV4::sub_accounts(0, ALICE), None);
+
+        // So can the IP Set itself
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            true,
+            (0, None),
+            None,
+            remark(b"sub account")
+        ));
+        assert_ok!(Balances::transfer(Origin::signed(BOB), sub_account, 500));
+
+        assert_ok!(INV4::sweep_sub_account(
+            Origin::signed(ips_account),
+            0,
+            ALICE
+        ));
+        assert_eq!(Balances::free_balance(sub_account), 0);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance + 1_000);
+        assert_eq!(INV4::sub_accounts(0, ALICE), None);
+    });
+}
+
+#[test]
+fn sweep_sub_account_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        create_ips(0, vec![]);
+
+        // Nothing was dispatched from ALICE's sub account yet
+        assert_noop!(
+            INV4::sweep_sub_account(Origin::signed(ALICE), 0, ALICE),
+            Error::<Runtime>::SubAccountNotFound
+        );
+
+        // A failed call doesn't register it
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            true,
+            (0, None),
+            None,
+            transfer(CHARLIE, 10_000_000)
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigExecuted { result: Err(_), .. })
+        ));
+        assert!(!System::events().iter().any(|record| matches!(
+            record.event,
+            mock::Event::INV4(crate::Event::SubAccountCreated { .. })
+        )));
+        assert_noop!(
+            INV4::sweep_sub_account(Origin::signed(ALICE), 0, ALICE),
+            Error::<Runtime>::SubAccountNotFound
+        );
+
+        // Only the IP Set or ALICE can sweep ALICE's sub account
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            true,
+            (0, None),
+            None,
+            remark(b"sub account")
+        ));
+        assert_noop!(
+            INV4::sweep_sub_account(Origin::signed(BOB), 0, ALICE),
+            Error::<Runtime>::NoPermission
+        );
+    });
+}
+
+#[test]
+fn spend_allowance_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(Balances::transfer(
+            Origin::signed(ALICE),
+            ips_account,
+            10_000
+        ));
+        assert_ok!(INV4::create_sub_token(
+            Origin::signed(ips_account),
+            0,
+            vec![((1, b"treasurer".to_vec().try_into().unwrap()), (BOB, 100))]
+        ));
+
+        // Without voting weight, BOB can only spend through the allowance
+        assert_ok!(INV4::set_sub_token_weight(
+            Origin::signed(ips_account),
+            0,
+            1,
+            OneOrPercent::ZeroPoint(Percent::zero())
+        ));
+
+        let allowance = SpendAllowance {
+            amount: 500,
+            period: 10,
+        };
+        assert_ok!(INV4::set_spend_allowance(
+            Origin::signed(ips_account),
+            0,
+            1,
+            Some(allowance)
+        ));
+        assert_eq!(INV4::spend_allowance(0, 1), Some(allowance));
+
+        let charlie_balance = Balances::free_balance(CHARLIE);
+
+        // Within the allowance, the transfer is executed without a vote
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(BOB),
+            false,
+            (0, Some(1)),
+            None,
+            transfer(CHARLIE, 300)
+        ));
+        System::assert_has_event(mock::Event::INV4(crate::Event::AllowanceSpent {
+            ips_id: 0,
+            sub_token_id: 1,
+            amount: 300,
+            spent_in_period: 300,
+        }));
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 300);
+        assert_eq!(INV4::spent_in_period(0, 1), Some((0, 300)));
+
+        // Over what is left of the allowance, a vote starts instead
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(BOB),
+            false,
+            (0, Some(1)),
+            None,
+            transfer(CHARLIE, 300)
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigVoteStarted { multisig_id: 1, .. })
+        ));
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 300);
+        assert_eq!(INV4::spent_in_period(0, 1), Some((0, 300)));
+
+        // Spending starts over in the next period
+        System::set_block_number(10);
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(BOB),
+            false,
+            (0, Some(1)),
+            None,
+            transfer(CHARLIE, 300)
+        ));
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 600);
+        assert_eq!(INV4::spent_in_period(0, 1), Some((1, 300)));
+
+        // Once the allowance is removed, every transfer needs a vote
+        assert_ok!(INV4::set_spend_allowance(
+            Origin::signed(ips_account),
+            0,
+            1,
+            None
+        ));
+        assert_eq!(INV4::spend_allowance(0, 1), None);
+        assert_eq!(INV4::spent_in_period(0, 1), None);
+
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(BOB),
+            false,
+            (0, Some(1)),
+            None,
+            transfer(CHARLIE, 100)
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigVoteStarted { multisig_id: 3, .. })
+        ));
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 600);
+    });
+}
+
+#[test]
+fn spend_allowance_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::create_sub_token(
+            Origin::signed(ips_account),
+            0,
+            vec![((1, b"treasurer".to_vec().try_into().unwrap()), (BOB, 100))]
+        ));
+
+        let allowance = SpendAllowance {
+            amount: 500,
+            period: 10,
+        };
+
+        // Only the IP Set account can set allowances
+        assert_noop!(
+            INV4::set_spend_allowance(Origin::signed(ALICE), 0, 1, Some(allowance)),
+            Error::<Runtime>::NoPermission
+        );
+
+        // IP Set doesn't exist
+        assert_noop!(
+            INV4::set_spend_allowance(Origin::signed(ips_account), 1, 1, Some(allowance)),
+            Error::<Runtime>::IpDoesntExist
+        );
+
+        // Sub token doesn't exist
+        assert_noop!(
+            INV4::set_spend_allowance(Origin::signed(ips_account), 0, 2, Some(allowance)),
+            Error::<Runtime>::SubAssetNotFound
+        );
+
+        // Periods can't be empty
+        assert_noop!(
+            INV4::set_spend_allowance(
+                Origin::signed(ips_account),
+                0,
+                1,
+                Some(SpendAllowance {
+                    amount: 500,
+                    period: 0,
+                })
+            ),
+            Error::<Runtime>::InvalidSpendPeriod
+        );
+    });
+}
+
+#[test]
+fn call_preimage_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+
+        let call = remark(b"preimage");
+        let call_hash = blake2_256(&call.encode());
+        let deposit = preimage_deposit(&call);
+
+        // The call is stored once, ALICE pays the deposit
+        for _ in 0..2 {
+            assert_ok!(INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                call.clone()
+            ));
+        }
+        let preimage = INV4::call_preimages(call_hash).unwrap();
+        assert_eq!(preimage.call.to_vec(), call.encode());
+        assert_eq!(preimage.depositor, ALICE);
+        assert_eq!(preimage.deposit, deposit);
+        assert_eq!(preimage.references, 2);
+        assert_eq!(Balances::reserved_balance(ALICE), 2 * 50 + deposit);
+
+        // BOB proposing it again only adds a reference
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(BOB),
+            false,
+            (0, None),
+            None,
+            call.clone()
+        ));
+        assert_eq!(INV4::call_preimages(call_hash).unwrap().references, 3);
+        assert_eq!(Balances::reserved_balance(BOB), 50);
+
+        // Canceling or executing an operation drops its reference
+        assert_ok!(INV4::withdraw_vote_multisig(
+            Origin::signed(ALICE),
+            (0, None),
+            0
+        ));
+        assert_eq!(INV4::call_preimages(call_hash).unwrap().references, 2);
+        assert_eq!(Balances::reserved_balance(ALICE), 50 + deposit);
+
+        assert_ok!(INV4::vote_multisig(Origin::signed(BOB), (0, None), 1));
+        assert_eq!(INV4::call_preimages(call_hash).unwrap().references, 1);
+        assert_eq!(Balances::reserved_balance(ALICE), deposit);
+
+        // The deposit is returned once no operation references the call
+        assert_ok!(INV4::withdraw_vote_multisig(
+            Origin::signed(BOB),
+            (0, None),
+            2
+        ));
+        assert!(INV4::call_preimages(call_hash).is_none());
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+    });
+}
+
+#[test]
+fn call_preimage_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ips_account = create_ips(0, vec![]);
+        assert_ok!(INV4::ipt_mint(
+            Origin::signed(ips_account),
+            (0, None),
+            1_000_000,
+            BOB
+        ));
+
+        // Calls going to a vote must fit in `MaxCallSize`
+        assert_noop!(
+            INV4::operate_multisig(
+                Origin::signed(ALICE),
+                false,
+                (0, None),
+                None,
+                remark(&[0u8; 2_000])
+            ),
+            Error::<Runtime>::CallTooLarge
+        );
+
+        // Calls executed right away aren't stored, so they can be larger
+        create_ips(1, vec![]);
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (1, None),
+            None,
+            remark(&[0u8; 2_000])
+        ));
+        assert_eq!(Balances::reserved_balance(ALICE), 0);
+    });
+}
+
+#[test]
+fn append_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipf_0 = mint_ipf(ALICE);
+        let ipf_1 = mint_ipf(ALICE);
+
+        // IPFs placed in an IP Set are owned by its account and locked
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipf_0)]);
+        assert_eq!(ipf_owner(ipf_0), ips_account);
+        assert_eq!(Ipf::locked_by(ipf_0), Some(IPS_LOCK_ID));
+        assert!(IpsAssets::<Runtime>::contains_key(0, AnyId::IpfId(ipf_0)));
+        assert!(AssetIpSets::<Runtime>::contains_key(AnyId::IpfId(ipf_0), 0));
+        assert_eq!(INV4::ips_asset_count(0), 1);
+
+        // ALICE appends an IPF through the sub account derived from ALICE, which owns it
+        assert_ok!(INV4::append(
+            Origin::signed(derive_ips_account::<Runtime>(0, Some(&ALICE))),
+            0,
+            Some(ALICE),
+            vec![AnyId::IpfId(ipf_1)],
+            None
+        ));
+        assert_eq!(ipf_owner(ipf_1), ips_account);
+        assert_eq!(Ipf::locked_by(ipf_1), Some(IPS_LOCK_ID));
+        assert!(IpsAssets::<Runtime>::contains_key(0, AnyId::IpfId(ipf_1)));
+        assert!(AssetIpSets::<Runtime>::contains_key(AnyId::IpfId(ipf_1), 0));
+        assert_eq!(INV4::ips_asset_count(0), 2);
+    });
+}
+
+#[test]
+fn append_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipf_0 = mint_ipf(ALICE);
+        let ipf_1 = mint_ipf(ALICE);
+        let ipf_2 = mint_ipf(ALICE);
+        let bob_ipf = mint_ipf(BOB);
+
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipf_0)]);
+        let alice_sub_account = derive_ips_account::<Runtime>(0, Some(&ALICE));
+
+        // IP Set doesn't exist
+        assert_noop!(
+            INV4::append(
+                Origin::signed(ips_account),
+                1,
+                None,
+                vec![AnyId::IpfId(ipf_1)],
+                None
+            ),
+            Error::<Runtime>::IpsNotFound
+        );
+
+        // Nothing to append
+        assert_noop!(
+            INV4::append(Origin::signed(ips_account), 0, None, vec![], None),
+            Error::<Runtime>::ValueNotChanged
+        );
+
+        // Only the IP Set can append to itself
+        assert_noop!(
+            INV4::append(
+                Origin::signed(ALICE),
+                0,
+                Some(ALICE),
+                vec![AnyId::IpfId(ipf_1)],
+                None
+            ),
+            Error::<Runtime>::NoPermission
+        );
+
+        // ALICE doesn't own BOB's IPF
+        assert_noop!(
+            INV4::append(
+                Origin::signed(alice_sub_account),
+                0,
+                Some(ALICE),
+                vec![AnyId::IpfId(bob_ipf)],
+                None
+            ),
+            Error::<Runtime>::NoPermission
+        );
+
+        // IP Sets can't hold other IP Sets
+        assert_noop!(
+            INV4::append(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![AnyId::IpsId(1)],
+                None
+            ),
+            Error::<Runtime>::IpsInsideIpsDisabled
+        );
+
+        // The IPF is already in the IP Set
+        assert_noop!(
+            INV4::append(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![AnyId::IpfId(ipf_0)],
+                None
+            ),
+            Error::<Runtime>::AssetAlreadyInIps
+        );
+
+        // Up to `MaxAssetsPerIps` assets
+        assert_ok!(INV4::append(
+            Origin::signed(alice_sub_account),
+            0,
+            Some(ALICE),
+            vec![AnyId::IpfId(ipf_1), AnyId::IpfId(ipf_2)],
+            None
+        ));
+        let ipf_3 = mint_ipf(ALICE);
+        assert_noop!(
+            INV4::append(
+                Origin::signed(alice_sub_account),
+                0,
+                Some(ALICE),
+                vec![AnyId::IpfId(ipf_3)],
+                None
+            ),
+            Error::<Runtime>::TooManyAssets
+        );
+    });
+}
+
+#[test]
+fn remove_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipf_0 = mint_ipf(ALICE);
+        let ipf_1 = mint_ipf(ALICE);
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipf_0), AnyId::IpfId(ipf_1)]);
+
+        // The IPF goes to BOB and is unlocked
+        assert_ok!(INV4::remove(
+            Origin::signed(ips_account),
+            0,
+            None,
+            vec![(AnyId::IpfId(ipf_0), BOB)],
+            None,
+            Some(0)
+        ));
+        assert_eq!(ipf_owner(ipf_0), BOB);
+        assert_eq!(Ipf::locked_by(ipf_0), None);
+        assert!(!IpsAssets::<Runtime>::contains_key(0, AnyId::IpfId(ipf_0)));
+        assert!(!AssetIpSets::<Runtime>::contains_key(
+            AnyId::IpfId(ipf_0),
+            0
+        ));
+        assert_eq!(INV4::ips_asset_count(0), 1);
+
+        // The other IPF stays in the IP Set
+        assert_eq!(ipf_owner(ipf_1), ips_account);
+        assert_eq!(Ipf::locked_by(ipf_1), Some(IPS_LOCK_ID));
+    });
+}
+
+#[test]
+fn remove_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipf_0 = mint_ipf(ALICE);
+        let ipf_1 = mint_ipf(ALICE);
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipf_0)]);
+
+        // IP Set doesn't exist
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ips_account),
+                1,
+                None,
+                vec![(AnyId::IpfId(ipf_0), BOB)],
+                None,
+                None
+            ),
+            Error::<Runtime>::IpsNotFound
+        );
+
+        // Only the IP Set can remove from itself
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ALICE),
+                0,
+                None,
+                vec![(AnyId::IpfId(ipf_0), BOB)],
+                None,
+                None
+            ),
+            Error::<Runtime>::NoPermission
+        );
+
+        // The IPF isn't in the IP Set
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![(AnyId::IpfId(ipf_1), BOB)],
+                None,
+                Some(0)
+            ),
+            Error::<Runtime>::NoPermission
+        );
+    });
+}
+
+/// IPFs authored by CHARLIE with a 10% royalty, then given to ALICE
+fn mint_royalty_ipfs(count: usize) -> Vec<u64> {
+    (0..count)
+        .map(|_| {
+            let ipf_id = mint_ipf(CHARLIE);
+            assert_ok!(Ipf::set_royalty(
+                Origin::signed(CHARLIE),
+                ipf_id,
+                Percent::from_percent(10)
+            ));
+            assert_ok!(Ipf::transfer(Origin::signed(CHARLIE), ipf_id, ALICE));
+
+            ipf_id
+        })
+        .collect()
+}
+
+#[test]
+fn remove_royalty_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        MultisigFee::set(0);
+        let ipfs = mint_royalty_ipfs(3);
+        let ips_account = create_ips(0, ipfs.iter().map(|id| AnyId::IpfId(*id)).collect());
+        assert_ok!(Balances::transfer(Origin::signed(BOB), ips_account, 10_000));
+
+        let charlie_balance = Balances::free_balance(CHARLIE);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        // The IP Set votes to sell two IPFs to BOB for 1_000, 500 each
+        assert_ok!(INV4::operate_multisig(
+            Origin::signed(ALICE),
+            false,
+            (0, None),
+            None,
+            Box::new(mock::Call::INV4(crate::Call::remove {
+                ips_id: 0,
+                original_caller: None,
+                assets: vec![(AnyId::IpfId(ipfs[0]), BOB), (AnyId::IpfId(ipfs[1]), BOB)],
+                new_metadata: None,
+                price: Some(1_000),
+            }))
+        ));
+        assert!(matches!(
+            last_event(),
+            mock::Event::INV4(crate::Event::MultisigExecuted { result: Ok(()), .. })
+        ));
+        for ipf_id in [ipfs[0], ipfs[1]] {
+            System::assert_has_event(mock::Event::Ipf(ipf::Event::RoyaltyPaid {
+                ipf_id,
+                payer: ips_account,
+                recipient: CHARLIE,
+                amount: 50,
+            }));
+            assert_eq!(ipf_owner(ipf_id), BOB);
+        }
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 100);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance - 100);
+
+        // Given away for nothing, the IPF pays no royalty
+        assert_ok!(INV4::remove(
+            Origin::signed(ips_account),
+            0,
+            None,
+            vec![(AnyId::IpfId(ipfs[2]), BOB)],
+            None,
+            Some(0)
+        ));
+        assert_eq!(ipf_owner(ipfs[2]), BOB);
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 100);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance - 100);
+    });
+}
+
+#[test]
+fn remove_royalty_uneven_price_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipfs = mint_royalty_ipfs(3);
+        let ips_account = create_ips(0, ipfs.iter().map(|id| AnyId::IpfId(*id)).collect());
+        assert_ok!(Balances::transfer(Origin::signed(BOB), ips_account, 10_000));
+
+        let charlie_balance = Balances::free_balance(CHARLIE);
+
+        // 314 splits into 104, 104 and 106 for the last IPF, so its royalty rounds up
+        assert_ok!(INV4::remove(
+            Origin::signed(ips_account),
+            0,
+            None,
+            ipfs.iter().map(|id| (AnyId::IpfId(*id), BOB)).collect(),
+            None,
+            Some(314)
+        ));
+        for (ipf_id, amount) in [(ipfs[0], 10), (ipfs[1], 10), (ipfs[2], 11)] {
+            System::assert_has_event(mock::Event::Ipf(ipf::Event::RoyaltyPaid {
+                ipf_id,
+                payer: ips_account,
+                recipient: CHARLIE,
+                amount,
+            }));
+        }
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 31);
+    });
+}
+
+#[test]
+fn remove_royalty_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipfs = mint_royalty_ipfs(1);
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipfs[0])]);
+
+        // IPFs can't leave without the price they were sold for
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![(AnyId::IpfId(ipfs[0]), BOB)],
+                None,
+                None
+            ),
+            Error::<Runtime>::PriceRequired
+        );
+
+        // The IP Set account can't afford the royalty, so the IPF stays in it
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![(AnyId::IpfId(ipfs[0]), BOB)],
+                None,
+                Some(1_000)
+            ),
+            pallet_balances::Error::<Runtime>::InsufficientBalance
+        );
+        assert_eq!(ipf_owner(ipfs[0]), ips_account);
+    });
+}
+
+#[test]
+fn create_replica_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipfs = mint_royalty_ipfs(2);
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipfs[0]), AnyId::IpfId(ipfs[1])]);
+        assert_ok!(INV4::allow_replica(Origin::signed(ips_account), 0, 1_000));
+        assert_eq!(INV4::replica_fee(0), Some(1_000));
+
+        let bob_balance = Balances::free_balance(BOB);
+        let charlie_balance = Balances::free_balance(CHARLIE);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        // BOB licenses the IP Set for the fee, CHARLIE gets the royalty of both IPFs on it
+        assert_ok!(INV4::create_replica(
+            Origin::signed(BOB),
+            0,
+            mock_license(),
+            OneOrPercent::One,
+            OneOrPercent::One,
+            true
+        ));
+        System::assert_last_event(mock::Event::INV4(crate::Event::ReplicaCreated {
+            ips_account: derive_ips_account::<Runtime>(1, None),
+            ips_id: 0,
+            replica_id: 1,
+        }));
+        for ipf_id in ipfs {
+            System::assert_has_event(mock::Event::Ipf(ipf::Event::RoyaltyPaid {
+                ipf_id,
+                payer: ips_account,
+                recipient: CHARLIE,
+                amount: 50,
+            }));
+        }
+        assert_eq!(Balances::free_balance(CHARLIE), charlie_balance + 100);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance + 900);
+        // BOB also sends the existential deposit to the replica account
+        assert_eq!(Balances::free_balance(BOB), bob_balance - 1_001);
+
+        let replica = INV4::ips_storage(1).unwrap();
+        assert_eq!(replica.ips_type, IpsType::Replica(0));
+        assert_eq!(replica.supply, 1);
+        assert_eq!(pallet::Balance::<Runtime>::get((1, None), BOB), Some(1));
+    });
+}
+
+#[test]
+fn create_replica_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipfs = mint_royalty_ipfs(1);
+        let ips_account = create_ips(0, vec![AnyId::IpfId(ipfs[0])]);
+
+        // Replicas aren't allowed
+        assert_noop!(
+            INV4::create_replica(
+                Origin::signed(BOB),
+                0,
+                mock_license(),
+                OneOrPercent::One,
+                OneOrPercent::One,
+                true
+            ),
+            Error::<Runtime>::ReplicaNotAllowed
+        );
+
+        // BOB can't afford the fee
+        assert_ok!(INV4::allow_replica(
+            Origin::signed(ips_account),
+            0,
+            2_000_000
+        ));
+        assert_noop!(
+            INV4::create_replica(
+                Origin::signed(BOB),
+                0,
+                mock_license(),
+                OneOrPercent::One,
+                OneOrPercent::One,
+                true
+            ),
+            pallet_balances::Error::<Runtime>::InsufficientBalance
+        );
+
+        // Disallowing replicas drops the fee
+        assert_ok!(INV4::disallow_replica(Origin::signed(ips_account), 0));
+        assert_eq!(INV4::replica_fee(0), None);
+    });
+}
+
+#[test]
+fn unsupported_asset_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ipf_0 = mint_ipf(ALICE);
+
+        // `()` handles no asset at all
+        assert_noop!(
+            <() as IpsAssetHandler<Runtime, u64>>::ensure_owner(&ipf_0, &ALICE),
+            Error::<Runtime>::UnsupportedAsset
+        );
+        assert_noop!(
+            <() as IpsAssetHandler<Runtime, u64>>::transfer_to_ips(&ipf_0, &ALICE, &BOB),
+            Error::<Runtime>::UnsupportedAsset
+        );
+        assert_noop!(
+            <() as IpsAssetHandler<Runtime, u64>>::transfer_from_ips(&ipf_0, &BOB, &ALICE),
+            Error::<Runtime>::UnsupportedAsset
+        );
+
+        // The mock runtime doesn't handle RMRK assets
+        #[cfg(feature = "rmrk")]
+        {
+            assert_noop!(
+                INV4::create_ips(
+                    Origin::signed(ALICE),
+                    MOCK_METADATA.to_vec(),
+                    vec![AnyId::RmrkNft((0, 0))],
+                    false,
+                    mock_license(),
+                    OneOrPercent::One,
+                    OneOrPercent::One,
+                    true
+                ),
+                Error::<Runtime>::UnsupportedAsset
+            );
+            assert_noop!(
+                INV4::create_ips(
+                    Origin::signed(ALICE),
+                    MOCK_METADATA.to_vec(),
+                    vec![AnyId::RmrkCollection(0)],
+                    false,
+                    mock_license(),
+                    OneOrPercent::One,
+                    OneOrPercent::One,
+                    true
+                ),
+                Error::<Runtime>::UnsupportedAsset
+            );
+        }
+    });
+}
+
+#[cfg(feature = "uniques")]
+#[test]
+fn uniques_asset_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Uniques::force_create(Origin::root(), 0, ALICE, true));
+        assert_ok!(Uniques::mint(Origin::signed(ALICE), 0, 0, ALICE));
+        assert_ok!(Uniques::mint(Origin::signed(ALICE), 0, 1, ALICE));
+
+        let ips_account = create_ips(0, vec![AnyId::UniquesItem(0, 0)]);
+        assert_eq!(Uniques::owner(0, 0), Some(ips_account));
+        assert!(IpsAssets::<Runtime>::contains_key(
+            0,
+            AnyId::UniquesItem(0, 0)
+        ));
+
+        assert_ok!(INV4::append(
+            Origin::signed(derive_ips_account::<Runtime>(0, Some(&ALICE))),
+            0,
+            Some(ALICE),
+            vec![AnyId::UniquesItem(0, 1)],
+            None
+        ));
+        assert_eq!(Uniques::owner(0, 1), Some(ips_account));
+        assert_eq!(INV4::ips_asset_count(0), 2);
+
+        assert_ok!(INV4::remove(
+            Origin::signed(ips_account),
+            0,
+            None,
+            vec![(AnyId::UniquesItem(0, 0), BOB)],
+            None,
+            None
+        ));
+        assert_eq!(Uniques::owner(0, 0), Some(BOB));
+        assert!(!IpsAssets::<Runtime>::contains_key(
+            0,
+            AnyId::UniquesItem(0, 0)
+        ));
+        assert_eq!(INV4::ips_asset_count(0), 1);
+    });
+}
+
+#[cfg(feature = "uniques")]
+#[test]
+fn uniques_asset_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Uniques::force_create(Origin::root(), 0, ALICE, true));
+        assert_ok!(Uniques::mint(Origin::signed(ALICE), 0, 0, BOB));
+
+        // ALICE doesn't own BOB's item
+        assert_noop!(
+            INV4::create_ips(
+                Origin::signed(ALICE),
+                MOCK_METADATA.to_vec(),
+                vec![AnyId::UniquesItem(0, 0)],
+                false,
+                mock_license(),
+                OneOrPercent::One,
+                OneOrPercent::One,
+                true
+            ),
+            Error::<Runtime>::NoPermission
+        );
+
+        // Item doesn't exist
+        assert_noop!(
+            INV4::create_ips(
+                Origin::signed(ALICE),
+                MOCK_METADATA.to_vec(),
+                vec![AnyId::UniquesItem(0, 1)],
+                false,
+                mock_license(),
+                OneOrPercent::One,
+                OneOrPercent::One,
+                true
+            ),
+            Error::<Runtime>::IpfNotFound
+        );
+
+        // The handler only moves items out of the account that holds them
+        assert_noop!(
+            <Runtime as Config>::UniquesAssets::transfer_from_ips(&(0, 0), &ALICE, &CHARLIE),
+            Error::<Runtime>::NoPermission
+        );
+        assert_noop!(
+            <Runtime as Config>::UniquesAssets::transfer_to_ips(&(0, 0), &ALICE, &CHARLIE),
+            Error::<Runtime>::NoPermission
+        );
+        assert_eq!(Uniques::owner(0, 0), Some(BOB));
+    });
+}
+
+#[cfg(feature = "assets")]
+#[test]
+fn fungible_asset_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Assets::force_create(Origin::root(), 0, ALICE, true, 1));
+        assert_ok!(Assets::mint(Origin::signed(ALICE), 0, ALICE, 1_000));
+
+        // Fungible assets are tracked in `FungibleHoldings`, not listed in `IpsAssets`
+        let ips_account = create_ips(0, vec![AnyId::FungibleAsset(0, 100)]);
+        assert_eq!(INV4::fungible_holdings(0, 0), Some(100));
+        assert_eq!(Assets::balance(0, ips_account), 100);
+        assert_eq!(INV4::ips_asset_count(0), 0);
+
+        assert_ok!(INV4::append(
+            Origin::signed(derive_ips_account::<Runtime>(0, Some(&ALICE))),
+            0,
+            Some(ALICE),
+            vec![AnyId::FungibleAsset(0, 50)],
+            None
+        ));
+        assert_eq!(INV4::fungible_holdings(0, 0), Some(150));
+        assert_eq!(Assets::balance(0, ips_account), 150);
+
+        assert_ok!(INV4::remove(
+            Origin::signed(ips_account),
+            0,
+            None,
+            vec![(AnyId::FungibleAsset(0, 40), BOB)],
+            None,
+            None
+        ));
+        assert_eq!(INV4::fungible_holdings(0, 0), Some(110));
+        assert_eq!(Assets::balance(0, ips_account), 110);
+        assert_eq!(Assets::balance(0, BOB), 40);
+
+        // Holdings are dropped once empty
+        assert_ok!(INV4::remove(
+            Origin::signed(ips_account),
+            0,
+            None,
+            vec![(AnyId::FungibleAsset(0, 110), BOB)],
+            None,
+            None
+        ));
+        assert_eq!(INV4::fungible_holdings(0, 0), None);
+        assert_eq!(Assets::balance(0, BOB), 150);
+    });
+}
+
+#[cfg(feature = "assets")]
+#[test]
+fn fungible_asset_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Assets::force_create(Origin::root(), 0, ALICE, true, 1));
+        assert_ok!(Assets::mint(Origin::signed(ALICE), 0, ALICE, 1_000));
+
+        let ips_account = create_ips(0, vec![AnyId::FungibleAsset(0, 100)]);
+
+        // More than the IP Set holds
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![(AnyId::FungibleAsset(0, 200), BOB)],
+                None,
+                None
+            ),
+            Error::<Runtime>::NotEnoughAmount
+        );
+
+        // Asset the IP Set doesn't hold
+        assert_noop!(
+            INV4::remove(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![(AnyId::FungibleAsset(1, 1), BOB)],
+                None,
+                None
+            ),
+            Error::<Runtime>::NoPermission
+        );
+
+        // Funds sent straight to the IP Set account can be appended, but only once
+        assert_ok!(Assets::transfer(Origin::signed(ALICE), 0, ips_account, 50));
+        assert_ok!(INV4::append(
+            Origin::signed(ips_account),
+            0,
+            None,
+            vec![AnyId::FungibleAsset(0, 50)],
+            None
+        ));
+        assert_eq!(INV4::fungible_holdings(0, 0), Some(150));
+        assert_noop!(
+            INV4::append(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![AnyId::FungibleAsset(0, 1)],
+                None
+            ),
+            Error::<Runtime>::NotEnoughAmount
+        );
+
+        // Nor can funds another IP Set on the same account already tracks
+        assert_ok!(Assets::transfer(Origin::signed(ALICE), 0, ips_account, 50));
+        IpsByOwner::<Runtime>::insert(ips_account, 1, ());
+        FungibleHoldings::<Runtime>::insert(1, 0, 50);
+        assert_noop!(
+            INV4::append(
+                Origin::signed(ips_account),
+                0,
+                None,
+                vec![AnyId::FungibleAsset(0, 1)],
+                None
+            ),
+            Error::<Runtime>::NotEnoughAmount
+        );
+    });
+}
+
+#[test]
+fn migrate_v1_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        StorageVersion::new(0).put::<INV4>();
+
+        // Before v1, IPFs were only owned by the IP Set account and listed in `data`
+        let ipf_0 = mint_ipf(ALICE);
+        let ips_account = derive_ips_account::<Runtime>(0, None);
+        assert_ok!(Ipf::send(ALICE, ipf_0, ips_account));
+        assert_ok!(Balances::transfer(Origin::signed(BOB), ips_account, 1_000));
+
+        unhashed::put(
+            &IpStorage::<Runtime>::hashed_key_for(0),
+            &v1::OldIpInfoOf::<Runtime> {
+                parentage: Parentage::Parent(ips_account),
+                metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
+                data: vec![v1::OldAnyId::IpfId(ipf_0), v1::OldAnyId::RmrkNft((0, 0))],
+                ips_type: IpsType::Normal,
+                allow_replica: false,
+                supply: 100,
+                license: mock_license().get_hash_and_metadata(),
+                execution_threshold: OneOrPercent::One,
+                default_asset_weight: OneOrPercent::One,
+                default_permission: true,
+            },
+        );
+        pallet::Balance::<Runtime>::insert((0, None), ALICE, 100);
+
+        // A vote ALICE paid a share of the fee into the IP Set account for
+        v1::Multisig::<Runtime>::insert(
+            0,
+            [0u8; 32],
+            v1::OldMultisigOperationOf::<Runtime> {
+                signers: vec![(ALICE, None)],
+                include_original_caller: false,
+                original_caller: ALICE,
+                actual_call: WrapperKeepOpaque::from_encoded(remark(b"old").encode()),
+                call_metadata: [0, 1],
+                call_weight: 0,
+                metadata: None,
+            },
+        );
+
+        let alice_balance = Balances::free_balance(ALICE);
+        let ips_balance = Balances::free_balance(ips_account);
+
+        v1::migrate::<Runtime>();
+        assert_eq!(INV4::on_chain_storage_version(), 1);
+
+        let info = INV4::ips_storage(0).unwrap();
+        assert_eq!(info.parentage, Parentage::Parent(ips_account));
+        assert_eq!(info.supply, 100);
+
+        // The IPF is listed and locked
+        assert!(IpsAssets::<Runtime>::contains_key(0, AnyId::IpfId(ipf_0)));
+        assert!(AssetIpSets::<Runtime>::contains_key(AnyId::IpfId(ipf_0), 0));
+        assert_eq!(Ipf::locked_by(ipf_0), Some(IPS_LOCK_ID));
+
+        // The RMRK NFT is only kept with the `rmrk` feature
+        #[cfg(feature = "rmrk")]
+        assert_eq!(INV4::ips_asset_count(0), 2);
+        #[cfg(not(feature = "rmrk"))]
+        assert_eq!(INV4::ips_asset_count(0), 1);
+
+        // The vote is dropped and ALICE gets the fee share back: 100 / 100 votes * 100 votes
+        assert_eq!(v1::Multisig::<Runtime>::iter().count(), 0);
+        assert_eq!(Balances::free_balance(ALICE), alice_balance + 100);
+        assert_eq!(Balances::free_balance(ips_account), ips_balance - 100);
+
+        // Running it again changes nothing
+        v1::migrate::<Runtime>();
+        assert_eq!(INV4::ips_storage(0), Some(info));
+        assert_eq!(Balances::free_balance(ALICE), alice_balance + 100);
+    });
+}
+
+#[test]
+fn migrate_v2_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        create_ips(0, vec![]);
+        StorageVersion::new(1).put::<INV4>();
+
+        // Before v2, sub tokens didn't keep track of their supply
+        unhashed::put(
+            &SubAssets::<Runtime>::hashed_key_for(0, 1),
+            &v2::OldSubIptInfoOf::<Runtime> {
+                id: 1,
+                metadata: b"sub token".to_vec().try_into().unwrap(),
+            },
+        );
+        pallet::Balance::<Runtime>::insert((0, Some(1)), BOB, 100);
+        pallet::Balance::<Runtime>::insert((0, Some(1)), CHARLIE, 50);
+
+        v2::migrate::<Runtime>();
+        assert_eq!(INV4::on_chain_storage_version(), 2);
+
+        let sub_token = INV4::sub_assets(0, 1).unwrap();
+        assert_eq!(sub_token.id, 1);
+        assert_eq!(sub_token.metadata.to_vec(), b"sub token".to_vec());
+        assert_eq!(sub_token.supply, 150);
+    });
+}
+
+#[test]
+fn migrate_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        StorageVersion::new(0).put::<INV4>();
+
+        let ips_account = derive_ips_account::<Runtime>(0, None);
+        unhashed::put(
+            &IpStorage::<Runtime>::hashed_key_for(0),
+            &v1::OldIpInfoOf::<Runtime> {
+                parentage: Parentage::Parent(ips_account),
+                metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
+                data: vec![],
+                ips_type: IpsType::Normal,
+                allow_replica: false,
+                supply: 1_000_000,
+                license: mock_license().get_hash_and_metadata(),
+                execution_threshold: OneOrPercent::One,
+                default_asset_weight: OneOrPercent::One,
+                default_permission: true,
+            },
+        );
+        unhashed::put(
+            &SubAssets::<Runtime>::hashed_key_for(0, 1),
+            &v2::OldSubIptInfoOf::<Runtime> {
+                id: 1,
+                metadata: b"sub token".to_vec().try_into().unwrap(),
+            },
+        );
+        pallet::Balance::<Runtime>::insert((0, Some(1)), BOB, 100);
+
+        // Both migrations run, in order
+        migrations::migrate::<Runtime>();
+        assert_eq!(
+            INV4::on_chain_storage_version(),
+            migrations::STORAGE_VERSION
+        );
+        assert_eq!(INV4::ips_storage(0).unwrap().supply, 1_000_000);
+        assert_eq!(INV4::sub_assets(0, 1).unwrap().supply, 100);
+    });
+}
+
+#[test]
+fn migrate_at_current_version_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        migrations::STORAGE_VERSION.put::<INV4>();
+
+        // Storage already at the current version is left as it is
+        let old_sub_token = v2::OldSubIptInfoOf::<Runtime> {
+            id: 1,
+            metadata: b"sub token".to_vec().try_into().unwrap(),
+        };
+        let key = SubAssets::<Runtime>::hashed_key_for(0, 1);
+        unhashed::put(&key, &old_sub_token);
+
+        migrations::migrate::<Runtime>();
+        assert_eq!(
+            INV4::on_chain_storage_version(),
+            migrations::STORAGE_VERSION
+        );
+        assert_eq!(unhashed::get_raw(&key), Some(old_sub_token.encode()));
+    });
+}
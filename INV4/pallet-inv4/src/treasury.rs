@@ -0,0 +1,149 @@
+use super::pallet::{self, *};
+use frame_support::{pallet_prelude::*, traits::IsSubType};
+use frame_system::{ensure_signed, pallet_prelude::*};
+use primitives::Parentage;
+use sp_arithmetic::traits::{CheckedAdd, Zero};
+
+/// Amount of the IP Set account balance a sub token can transfer out per period without a vote
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct SpendAllowance<Balance, BlockNumber> {
+    /// Maximum amount that can be transferred during a single period
+    pub amount: Balance,
+    /// Length of a period in blocks
+    pub period: BlockNumber,
+}
+
+pub type SpendAllowanceOf<T> =
+    SpendAllowance<<T as pallet::Config>::Balance, <T as frame_system::Config>::BlockNumber>;
+
+impl<T: Config> Pallet<T> {
+    /// Set (or remove with `None`) the spend allowance of a sub token
+    pub(crate) fn inner_set_spend_allowance(
+        owner: OriginFor<T>,
+        ips_id: T::IpId,
+        sub_token_id: T::IpId,
+        allowance: Option<SpendAllowanceOf<T>>,
+    ) -> DispatchResult {
+        let owner = ensure_signed(owner)?;
+
+        let ip = IpStorage::<T>::get(ips_id).ok_or(Error::<T>::IpDoesntExist)?;
+
+        // Only the top-level IP Set can set spend allowances
+        match ip.parentage {
+            Parentage::Parent(ips_account) => {
+                ensure!(ips_account == owner, Error::<T>::NoPermission)
+            }
+            Parentage::Child(..) => return Err(Error::<T>::NotParent.into()),
+        }
+
+        ensure!(
+            SubAssets::<T>::contains_key(ips_id, sub_token_id),
+            Error::<T>::SubAssetNotFound
+        );
+
+        if let Some(allowance) = allowance {
+            ensure!(!allowance.period.is_zero(), Error::<T>::InvalidSpendPeriod);
+
+            SpendAllowances::<T>::insert(ips_id, sub_token_id, allowance);
+        } else {
+            SpendAllowances::<T>::remove(ips_id, sub_token_id);
+        }
+
+        // Spending is tracked from scratch under the new allowance
+        SpentInPeriod::<T>::remove(ips_id, sub_token_id);
+
+        Self::deposit_event(Event::SpendAllowanceSet {
+            ips_id,
+            sub_token_id,
+            allowance,
+        });
+
+        Ok(())
+    }
+
+    /// If `call` is a balance transfer from the IP Set account that fits in what is left of the sub token's
+    /// allowance for the current period, returns the amount being transferred.
+    pub(crate) fn covered_by_spend_allowance(
+        ipt_id: (T::IpId, Option<T::IpId>),
+        include_caller: bool,
+        call: &<T as pallet::Config>::Call,
+    ) -> Option<<T as pallet::Config>::Balance> {
+        // Allowances only apply to sub tokens spending from the IP Set account itself
+        let sub_token_id = ipt_id.1?;
+        if include_caller {
+            return None;
+        }
+
+        let balances_call: &pallet_balances::Call<T> = call.is_sub_type()?;
+
+        let amount: <T as pallet::Config>::Balance = match balances_call {
+            pallet_balances::Call::transfer { value, .. }
+            | pallet_balances::Call::transfer_keep_alive { value, .. } => (*value).into(),
+            _ => return None,
+        };
+
+        let allowance = SpendAllowances::<T>::get(ipt_id.0, sub_token_id)?;
+
+        let already_spent =
+            Pallet::<T>::spent_in_current_period(ipt_id.0, sub_token_id, &allowance);
+
+        if already_spent.checked_add(&amount)? <= allowance.amount {
+            Some(amount)
+        } else {
+            None
+        }
+    }
+
+    /// Record `amount` as spent by a sub token during the current period
+    pub(crate) fn record_spend(
+        ips_id: T::IpId,
+        sub_token_id: T::IpId,
+        amount: <T as pallet::Config>::Balance,
+    ) -> DispatchResult {
+        let allowance =
+            SpendAllowances::<T>::get(ips_id, sub_token_id).ok_or(Error::<T>::NoPermission)?;
+
+        let spent = Pallet::<T>::spent_in_current_period(ips_id, sub_token_id, &allowance)
+            .checked_add(&amount)
+            .ok_or(Error::<T>::Overflow)?;
+
+        ensure!(
+            spent <= allowance.amount,
+            Error::<T>::SpendAllowanceExceeded
+        );
+
+        SpentInPeriod::<T>::insert(
+            ips_id,
+            sub_token_id,
+            (Pallet::<T>::current_spend_period(&allowance), spent),
+        );
+
+        Self::deposit_event(Event::AllowanceSpent {
+            ips_id,
+            sub_token_id,
+            amount,
+            spent_in_period: spent,
+        });
+
+        Ok(())
+    }
+
+    /// Amount spent by a sub token during the current period, 0 if the last recorded spend was in a past period
+    pub fn spent_in_current_period(
+        ips_id: T::IpId,
+        sub_token_id: T::IpId,
+        allowance: &SpendAllowanceOf<T>,
+    ) -> <T as pallet::Config>::Balance {
+        match SpentInPeriod::<T>::get(ips_id, sub_token_id) {
+            Some((period, spent)) if period == Pallet::<T>::current_spend_period(allowance) => {
+                spent
+            }
+            _ => Zero::zero(),
+        }
+    }
+
+    /// Index of the period the current block belongs to
+    fn current_spend_period(allowance: &SpendAllowanceOf<T>) -> T::BlockNumber {
+        frame_system::Pallet::<T>::block_number() / allowance.period
+    }
+}
@@ -11,16 +11,30 @@
 //! ### Pallet Functions
 //!
 //! `mint` - Create a new IP Token and add to an IP Set
+//! `mint_batch` - Create several IP Tokens at once, optionally in a new IP Set
 //! `burn` - Burn an IP Token from an IP Set
-//! `amend` - Amend the data stored inside an IP Token
+//! `transfer` - Transfer an IP Token to another account
+//! `amend` - Amend the data stored inside an IP Token, keeping its previous versions
+//! `set_royalty` - Set the share of sales and licensing paid to an IP Token's author
+//! `transfer_authorship` - Make another account the author of an IP Token
+//! `invite_co_author` - Invite an account to co-author an IP Token with a share of its royalties
+//! `accept_co_authorship` - Accept an invitation to co-author an IP Token
+//! `remove_co_author` - Remove a co-author or invitation from an IP Token
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::unused_unit)]
 
-use frame_support::{ensure, traits::Get, BoundedVec, Parameter};
+use frame_support::{
+    ensure,
+    traits::{Currency, ExistenceRequirement, Get, LockIdentifier, ReservableCurrency},
+    BoundedVec, Parameter,
+};
 use frame_system::{ensure_signed, pallet_prelude::OriginFor};
-use primitives::IpfInfo;
-use sp_runtime::traits::{AtLeast32BitUnsigned, CheckedAdd, Member, One};
+use primitives::{Cid, CoAuthor, IpfInfo, IpfVersion};
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, CheckedAdd, Member, One, Saturating, Zero},
+    Percent,
+};
 use sp_std::{convert::TryInto, vec::Vec};
 
 #[cfg(test)]
@@ -28,6 +42,11 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+pub mod migrations;
+pub mod traits;
+
+pub use traits::IpsCreator;
+
 pub use pallet::*;
 
 #[frame_support::pallet]
@@ -36,6 +55,7 @@ pub mod pallet {
     use frame_support::pallet_prelude::*;
 
     #[pallet::pallet]
+    #[pallet::storage_version(crate::migrations::STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     #[pallet::config]
@@ -47,19 +67,56 @@ pub mod pallet {
         type IpfId: Parameter + Member + AtLeast32BitUnsigned + Default + Copy + MaxEncodedLen;
         /// The maximum size of an IPF's metadata
         type MaxIpfMetadata: Get<u32>;
+        /// The maximum number of previous versions kept for an IPF
+        type MaxIpfVersions: Get<u32>;
+        /// What happens when an IPF is minted or amended with content another IPF already has
+        type OnDuplicateContent: Get<DuplicateContent>;
+        /// Currency royalties are paid in and metadata deposits are reserved in
+        type Currency: ReservableCurrency<Self::AccountId>;
+        /// Deposit reserved for storing an IPF's metadata, whatever its size
+        type MetadataDepositBase: Get<BalanceOf<Self>>;
+        /// Deposit reserved for each byte of an IPF's metadata
+        type MetadataDepositPerByte: Get<BalanceOf<Self>>;
+        /// The highest royalty an author can set on an IPF
+        type MaxRoyalty: Get<Percent>;
+        /// The maximum number of co-authors and pending invitations of an IPF
+        type MaxCoAuthors: Get<u32>;
+        /// The maximum number of IPFs minted by one `mint_batch` call
+        type MaxIpfsPerBatch: Get<u32>;
+        /// Creates IP Sets for `mint_batch`
+        type IpsCreator: IpsCreator<Self::AccountId, Self::IpfId>;
+    }
+
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+    /// Handling of IPFs whose content is already held by another IPF
+    #[derive(Clone, Copy, Eq, PartialEq, Debug)]
+    pub enum DuplicateContent {
+        /// Fail with `DuplicateContent`
+        Reject,
+        /// Allow it, recording the IPF as a derivative of the first IPF with that content
+        Derivative,
     }
 
     pub type IpfMetadataOf<T> = BoundedVec<u8, <T as Config>::MaxIpfMetadata>;
-    pub type IpfInfoOf<T> = IpfInfo<
+    pub type IpfInfoOf<T> = IpfInfo<<T as frame_system::Config>::AccountId, Cid, IpfMetadataOf<T>>;
+
+    pub type IpfVersionOf<T> = IpfVersion<
         <T as frame_system::Config>::AccountId,
-        <T as frame_system::Config>::Hash, // CID stored as just the hash
-        IpfMetadataOf<T>,
+        Cid,
+        <T as frame_system::Config>::BlockNumber,
     >;
 
+    pub type IpsParamsOf<T> = <<T as Config>::IpsCreator as IpsCreator<
+        <T as frame_system::Config>::AccountId,
+        <T as Config>::IpfId,
+    >>::Params;
+
     pub type GenesisIpfData<T> = (
         <T as frame_system::Config>::AccountId, // IPF owner
         Vec<u8>,                                // IPF metadata
-        <T as frame_system::Config>::Hash,      // CID stored as just the hash
+        Vec<u8>,                                // IPF data, a binary CID
     );
 
     /// Next available IPF ID
@@ -86,88 +143,591 @@ pub mod pallet {
         (),
     >;
 
+    /// Previous versions of an IPF, oldest first
+    ///
+    /// Only the latest `MaxIpfVersions` amendments are kept
+    #[pallet::storage]
+    #[pallet::getter(fn ipf_versions)]
+    pub type IpfVersions<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::IpfId,
+        BoundedVec<IpfVersionOf<T>, T::MaxIpfVersions>,
+        ValueQuery,
+    >;
+
+    /// First IPF holding some content, keyed by its CIDv1
+    #[pallet::storage]
+    #[pallet::getter(fn ipf_by_content)]
+    pub type IpfByContent<T: Config> = StorageMap<_, Blake2_128Concat, Cid, T::IpfId>;
+
+    /// IPFs holding content that was already held by another IPF, pointing to that IPF
+    #[pallet::storage]
+    #[pallet::getter(fn derivative_of)]
+    pub type DerivativeOf<T: Config> = StorageMap<_, Blake2_128Concat, T::IpfId, T::IpfId>;
+
+    /// Share of every sale or licensing of an IPF paid to its author
+    #[pallet::storage]
+    #[pallet::getter(fn royalty)]
+    pub type Royalties<T: Config> = StorageMap<_, Blake2_128Concat, T::IpfId, Percent>;
+
+    /// Co-authors of an IPF and accounts invited to co-author it
+    #[pallet::storage]
+    #[pallet::getter(fn co_authors)]
+    pub type CoAuthors<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::IpfId,
+        BoundedVec<CoAuthor<T::AccountId>, T::MaxCoAuthors>,
+        ValueQuery,
+    >;
+
+    /// Account that reserved the deposit for an IPF's metadata, and the amount reserved.
+    /// IPFs minted before deposits were introduced have none
+    #[pallet::storage]
+    #[pallet::getter(fn metadata_deposit)]
+    pub type MetadataDeposits<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::IpfId, (T::AccountId, BalanceOf<T>)>;
+
+    /// Pallet holding a lock on an IPF, which can't be burned or change owner until it's unlocked
+    #[pallet::storage]
+    #[pallet::getter(fn locked_by)]
+    pub type IpfLocks<T: Config> = StorageMap<_, Blake2_128Concat, T::IpfId, LockIdentifier>;
+
+    /// IPFs minted at genesis take no metadata deposit, so their owners don't need to be funded
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        /// IPFs to mint, in order of their IDs
+        pub ipfs: Vec<GenesisIpfData<T>>,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self {
+                ipfs: Default::default(),
+            }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            for (owner, metadata, data) in &self.ipfs {
+                let metadata: IpfMetadataOf<T> = metadata
+                    .clone()
+                    .try_into()
+                    .expect("Genesis IPF metadata is too long");
+                let data = Cid::from_bytes(data).expect("Genesis IPF data must be a valid CID");
+
+                Pallet::<T>::create(owner.clone(), metadata, data, T::OnDuplicateContent::get())
+                    .expect("Genesis IPFs must have unique content unless duplicates are allowed");
+            }
+        }
+    }
+
     /// Errors for IPF pallet
     #[pallet::error]
     pub enum Error<T> {
         /// No available IPF ID
         NoAvailableIpfId,
-        /// IPF (IpsId, IpfId) not found
+        /// IPF not found
         IpfNotFound,
         /// The operator is not the owner of the IPF and has no permission
         NoPermission,
         /// Failed because the Maximum amount of metadata was exceeded
         MaxMetadataExceeded,
+        /// IPF data is not a valid binary CIDv0 or CIDv1
+        InvalidCid,
+        /// Another IPF already holds this content
+        DuplicateContent,
+        /// The operator is not the author of the IPF
+        NotAuthor,
+        /// Royalty is over `MaxRoyalty`
+        RoyaltyTooHigh,
+        /// The account is already a co-author of the IPF or invited to be one
+        AlreadyCoAuthor,
+        /// The account is not a co-author of the IPF nor invited to be one
+        NotCoAuthor,
+        /// Max amount of co-authors for the IPF reached
+        TooManyCoAuthors,
+        /// Co-author shares would add up to more than 100%
+        SharesExceeded,
+        /// The IPF is locked, it can only be burned or moved by whoever locked it
+        IpfLocked,
     }
 
     #[pallet::event]
     #[pallet::generate_deposit(fn deposit_event)]
-    //#[pallet::metadata(T::AccountId = "AccountId", T::IpfId = "IpfId", T::Hash = "Hash")]
     pub enum Event<T: Config> {
-        Minted(T::AccountId, T::IpfId, T::Hash),
-        Burned(T::AccountId, T::IpfId),
+        /// An IPF was minted
+        Minted {
+            owner: T::AccountId,
+            ipf_id: T::IpfId,
+            data: Cid,
+        },
+        /// An IPF was burned
+        Burned {
+            owner: T::AccountId,
+            ipf_id: T::IpfId,
+        },
+        /// The data of an IPF was replaced, the previous data was added to its versions
+        Amended {
+            editor: T::AccountId,
+            ipf_id: T::IpfId,
+            data: Cid,
+        },
+        /// The metadata of an IPF changed
+        MetadataUpdated {
+            ipf_id: T::IpfId,
+            metadata: IpfMetadataOf<T>,
+        },
+        /// An IPF changed owner
+        Transferred {
+            from: T::AccountId,
+            to: T::AccountId,
+            ipf_id: T::IpfId,
+        },
+        /// The royalty of an IPF was set
+        RoyaltySet { ipf_id: T::IpfId, royalty: Percent },
+        /// Royalty on an IPF was paid to one of its authors
+        RoyaltyPaid {
+            ipf_id: T::IpfId,
+            payer: T::AccountId,
+            recipient: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// Authorship of an IPF was given to another account
+        AuthorshipTransferred {
+            ipf_id: T::IpfId,
+            from: T::AccountId,
+            to: T::AccountId,
+        },
+        /// An account was invited to co-author an IPF
+        CoAuthorInvited {
+            ipf_id: T::IpfId,
+            co_author: T::AccountId,
+            share: Percent,
+        },
+        /// An account accepted to co-author an IPF
+        CoAuthorAccepted {
+            ipf_id: T::IpfId,
+            co_author: T::AccountId,
+            share: Percent,
+        },
+        /// A co-author or invitation was removed from an IPF
+        CoAuthorRemoved {
+            ipf_id: T::IpfId,
+            co_author: T::AccountId,
+        },
     }
 
     /// Dispatch functions
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         /// Mint IPF(Intellectual Property File) to `owner`.
-        /// i.e. create IP File
+        /// i.e. create IP File, reserving a deposit from `owner` for its metadata
         #[pallet::weight(300_000_000)]
         pub fn mint(
             owner: OriginFor<T>,
             metadata: Vec<u8>,
-            data: T::Hash,
+            data: Vec<u8>,
         ) -> DispatchResultWithPostInfo {
-            NextIpfId::<T>::try_mutate(|id| -> DispatchResultWithPostInfo {
+            let owner = ensure_signed(owner)?;
+
+            Self::inner_mint(owner, metadata, data)?;
+
+            Ok(().into())
+        }
+
+        /// Mint several IPFs to `owner` with consecutive IDs, each from a `(metadata, data)` pair.
+        /// With `ips` set, an IP Set holding them is created through `IpsCreator`
+        #[pallet::weight(300_000_000u64.saturating_mul(ipfs.len() as u64).saturating_add(
+            if ips.is_some() { 900_000_000 } else { 0 }
+        ))]
+        pub fn mint_batch(
+            owner: OriginFor<T>,
+            ipfs: BoundedVec<(Vec<u8>, Vec<u8>), T::MaxIpfsPerBatch>,
+            ips: Option<IpsParamsOf<T>>,
+        ) -> DispatchResult {
+            let owner = ensure_signed(owner)?;
+
+            let ipf_ids = ipfs
+                .into_iter()
+                .map(|(metadata, data)| Self::inner_mint(owner.clone(), metadata, data))
+                .collect::<Result<Vec<T::IpfId>, DispatchError>>()?;
+
+            if let Some(params) = ips {
+                T::IpsCreator::create_ips(owner, ipf_ids, params)?;
+            }
+
+            Ok(())
+        }
+
+        /// Burn IPF(Intellectual Property File) from `owner`.
+        /// i.e. delete IP file
+        #[pallet::weight(300_000_000)]
+        pub fn burn(owner: OriginFor<T>, ipf_id: T::IpfId) -> DispatchResult {
+            IpfStorage::<T>::try_mutate(ipf_id, |ipf_info| -> DispatchResult {
                 let owner = ensure_signed(owner)?;
+                let t = ipf_info.take().ok_or(Error::<T>::IpfNotFound)?;
+                ensure!(t.owner == owner, Error::<T>::NoPermission);
+                ensure!(!IpfLocks::<T>::contains_key(ipf_id), Error::<T>::IpfLocked);
+
+                IpfByOwner::<T>::remove(owner.clone(), ipf_id);
+                IpfVersions::<T>::remove(ipf_id);
+                Royalties::<T>::remove(ipf_id);
+                CoAuthors::<T>::remove(ipf_id);
+                Self::unregister_content(ipf_id, &t.data);
+                Self::release_metadata_deposit(ipf_id);
+
+                Self::deposit_event(Event::Burned { owner, ipf_id });
+
+                Ok(())
+            })
+        }
+
+        /// Replace the data and metadata of an IPF owned by `owner`.
+        /// The previous data is kept in the IPF's version history
+        #[pallet::weight(300_000_000)]
+        pub fn amend(
+            owner: OriginFor<T>,
+            ipf_id: T::IpfId,
+            metadata: Vec<u8>,
+            data: Vec<u8>,
+        ) -> DispatchResult {
+            IpfStorage::<T>::try_mutate(ipf_id, |ipf_info| -> DispatchResult {
+                let owner = ensure_signed(owner)?;
+                let info = ipf_info.as_mut().ok_or(Error::<T>::IpfNotFound)?;
+                ensure!(info.owner == owner, Error::<T>::NoPermission);
+
                 let bounded_metadata: BoundedVec<u8, T::MaxIpfMetadata> = metadata
                     .try_into()
                     .map_err(|_| Error::<T>::MaxMetadataExceeded)?;
+                let data = Cid::from_bytes(&data).map_err(|_| Error::<T>::InvalidCid)?;
+
+                if info.metadata != bounded_metadata {
+                    // The editor pays for the new metadata
+                    Self::release_metadata_deposit(ipf_id);
+                    Self::reserve_metadata_deposit(ipf_id, &owner, bounded_metadata.len())?;
+
+                    info.metadata = bounded_metadata.clone();
+
+                    Self::deposit_event(Event::MetadataUpdated {
+                        ipf_id,
+                        metadata: bounded_metadata,
+                    });
+                }
 
+                // Only a change of data makes a new version
+                if info.data != data {
+                    Self::unregister_content(ipf_id, &info.data);
+                    Self::register_content(ipf_id, &data, T::OnDuplicateContent::get())?;
+
+                    IpfVersions::<T>::mutate(ipf_id, |versions| {
+                        // Drop the oldest version to make room
+                        if !versions.is_empty() && versions.len() as u32 >= T::MaxIpfVersions::get()
+                        {
+                            versions.remove(0);
+                        }
+
+                        let _ = versions.try_push(IpfVersion {
+                            data: info.data.clone(),
+                            amended_at: frame_system::Pallet::<T>::block_number(),
+                            editor: owner.clone(),
+                        });
+                    });
+
+                    info.data = data.clone();
+
+                    Self::deposit_event(Event::Amended {
+                        editor: owner,
+                        ipf_id,
+                        data,
+                    });
+                }
+
+                Ok(())
+            })
+        }
+
+        /// Transfer IPF(Intellectual Property File) from `owner` to `target`.
+        #[pallet::weight(300_000_000)]
+        pub fn transfer(
+            owner: OriginFor<T>,
+            ipf_id: T::IpfId,
+            target: T::AccountId,
+        ) -> DispatchResult {
+            let owner = ensure_signed(owner)?;
+
+            Self::send(owner, ipf_id, target)
+        }
+
+        /// Set the royalty paid to the author of an IPF whenever it's sold or licensed.
+        /// Only the author can set it
+        #[pallet::weight(100_000_000)]
+        pub fn set_royalty(
+            author: OriginFor<T>,
+            ipf_id: T::IpfId,
+            royalty: Percent,
+        ) -> DispatchResult {
+            let author = ensure_signed(author)?;
+            let info = IpfStorage::<T>::get(ipf_id).ok_or(Error::<T>::IpfNotFound)?;
+            ensure!(info.author == author, Error::<T>::NotAuthor);
+            ensure!(royalty <= T::MaxRoyalty::get(), Error::<T>::RoyaltyTooHigh);
+
+            if royalty.is_zero() {
+                Royalties::<T>::remove(ipf_id);
+            } else {
+                Royalties::<T>::insert(ipf_id, royalty);
+            }
+
+            Self::deposit_event(Event::RoyaltySet { ipf_id, royalty });
+
+            Ok(())
+        }
+
+        /// Make `new_author` the author of an IPF, receiving its royalties from then on.
+        /// Only the current author can give authorship away, and not to one of the IPF's co-authors
+        #[pallet::weight(100_000_000)]
+        pub fn transfer_authorship(
+            author: OriginFor<T>,
+            ipf_id: T::IpfId,
+            new_author: T::AccountId,
+        ) -> DispatchResult {
+            IpfStorage::<T>::try_mutate(ipf_id, |ipf_info| -> DispatchResult {
+                let author = ensure_signed(author)?;
+                let info = ipf_info.as_mut().ok_or(Error::<T>::IpfNotFound)?;
+                ensure!(info.author == author, Error::<T>::NotAuthor);
+                // The author can't also hold a co-author share of their own royalty
+                ensure!(
+                    !CoAuthors::<T>::get(ipf_id)
+                        .iter()
+                        .any(|c| c.account == new_author),
+                    Error::<T>::AlreadyCoAuthor
+                );
+
+                info.author = new_author.clone();
+
+                Self::deposit_event(Event::AuthorshipTransferred {
+                    ipf_id,
+                    from: author,
+                    to: new_author,
+                });
+
+                Ok(())
+            })
+        }
+
+        /// Invite `co_author` to co-author an IPF, receiving `share` of its royalties once they accept.
+        /// Only the author can invite co-authors
+        #[pallet::weight(100_000_000)]
+        pub fn invite_co_author(
+            author: OriginFor<T>,
+            ipf_id: T::IpfId,
+            co_author: T::AccountId,
+            share: Percent,
+        ) -> DispatchResult {
+            let author = ensure_signed(author)?;
+            let info = IpfStorage::<T>::get(ipf_id).ok_or(Error::<T>::IpfNotFound)?;
+            ensure!(info.author == author, Error::<T>::NotAuthor);
+            ensure!(co_author != info.author, Error::<T>::AlreadyCoAuthor);
+
+            CoAuthors::<T>::try_mutate(ipf_id, |co_authors| -> DispatchResult {
+                ensure!(
+                    !co_authors.iter().any(|c| c.account == co_author),
+                    Error::<T>::AlreadyCoAuthor
+                );
+
+                // Shares of pending invitations count too, so accepting can't go over 100%
+                let total_shares = co_authors
+                    .iter()
+                    .map(|c| c.share.deconstruct() as u32)
+                    .sum::<u32>()
+                    + share.deconstruct() as u32;
+                ensure!(total_shares <= 100, Error::<T>::SharesExceeded);
+
+                co_authors
+                    .try_push(CoAuthor {
+                        account: co_author.clone(),
+                        share,
+                        accepted: false,
+                    })
+                    .map_err(|_| Error::<T>::TooManyCoAuthors)?;
+
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::CoAuthorInvited {
+                ipf_id,
+                co_author,
+                share,
+            });
+
+            Ok(())
+        }
+
+        /// Accept an invitation to co-author an IPF
+        #[pallet::weight(100_000_000)]
+        pub fn accept_co_authorship(co_author: OriginFor<T>, ipf_id: T::IpfId) -> DispatchResult {
+            let co_author = ensure_signed(co_author)?;
+            ensure!(
+                IpfStorage::<T>::contains_key(ipf_id),
+                Error::<T>::IpfNotFound
+            );
+
+            let share = CoAuthors::<T>::try_mutate(ipf_id, |co_authors| {
+                let invitation = co_authors
+                    .iter_mut()
+                    .find(|c| c.account == co_author && !c.accepted)
+                    .ok_or(Error::<T>::NotCoAuthor)?;
+
+                invitation.accepted = true;
+
+                Ok::<Percent, Error<T>>(invitation.share)
+            })?;
+
+            Self::deposit_event(Event::CoAuthorAccepted {
+                ipf_id,
+                co_author,
+                share,
+            });
+
+            Ok(())
+        }
+
+        /// Remove a co-author or invitation from an IPF.
+        /// Either the author or the co-author themselves can do it
+        #[pallet::weight(100_000_000)]
+        pub fn remove_co_author(
+            caller: OriginFor<T>,
+            ipf_id: T::IpfId,
+            co_author: T::AccountId,
+        ) -> DispatchResult {
+            let caller = ensure_signed(caller)?;
+            let info = IpfStorage::<T>::get(ipf_id).ok_or(Error::<T>::IpfNotFound)?;
+            ensure!(
+                caller == info.author || caller == co_author,
+                Error::<T>::NoPermission
+            );
+
+            CoAuthors::<T>::try_mutate(ipf_id, |co_authors| -> DispatchResult {
+                let index = co_authors
+                    .iter()
+                    .position(|c| c.account == co_author)
+                    .ok_or(Error::<T>::NotCoAuthor)?;
+
+                co_authors.remove(index);
+
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::CoAuthorRemoved { ipf_id, co_author });
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Create an IPF owned and authored by `owner`, returning its ID
+        pub(crate) fn inner_mint(
+            owner: T::AccountId,
+            metadata: Vec<u8>,
+            data: Vec<u8>,
+        ) -> Result<T::IpfId, DispatchError> {
+            let bounded_metadata: BoundedVec<u8, T::MaxIpfMetadata> = metadata
+                .try_into()
+                .map_err(|_| Error::<T>::MaxMetadataExceeded)?;
+            let data = Cid::from_bytes(&data).map_err(|_| Error::<T>::InvalidCid)?;
+            let metadata_len = bounded_metadata.len();
+
+            let ipf_id = Self::create(
+                owner.clone(),
+                bounded_metadata,
+                data,
+                T::OnDuplicateContent::get(),
+            )?;
+            Self::reserve_metadata_deposit(ipf_id, &owner, metadata_len)?;
+
+            Ok(ipf_id)
+        }
+
+        /// Create an IPF without metadata holding `data` on behalf of another pallet, e.g. the contract code
+        /// of an IP Set made by `pallet-ipvm`. No metadata deposit is taken, and content another IPF already
+        /// holds makes it a derivative of that IPF whatever `OnDuplicateContent` is
+        pub fn mint_derivable(owner: T::AccountId, data: Cid) -> Result<T::IpfId, DispatchError> {
+            Self::create(
+                owner,
+                Default::default(),
+                data,
+                DuplicateContent::Derivative,
+            )
+        }
+
+        /// Store a new IPF owned and authored by `owner`, returning its ID
+        fn create(
+            owner: T::AccountId,
+            metadata: IpfMetadataOf<T>,
+            data: Cid,
+            on_duplicate: DuplicateContent,
+        ) -> Result<T::IpfId, DispatchError> {
+            NextIpfId::<T>::try_mutate(|id| -> Result<T::IpfId, DispatchError> {
                 let ipf_id = *id;
                 *id = id
                     .checked_add(&One::one())
                     .ok_or(Error::<T>::NoAvailableIpfId)?;
 
+                Self::register_content(ipf_id, &data, on_duplicate)?;
+
                 let ipf_info = IpfInfo {
-                    metadata: bounded_metadata,
+                    metadata,
                     owner: owner.clone(),
                     author: owner.clone(),
-                    data,
+                    data: data.clone(),
                 };
                 IpfStorage::<T>::insert(ipf_id, ipf_info);
                 IpfByOwner::<T>::insert(owner.clone(), ipf_id, ());
 
-                Self::deposit_event(Event::Minted(owner, ipf_id, data));
+                Self::deposit_event(Event::Minted {
+                    owner,
+                    ipf_id,
+                    data,
+                });
 
-                Ok(().into())
+                Ok(ipf_id)
             })
         }
 
-        /// Burn IPF(Intellectual Property File) from `owner`.
-        /// i.e. delete IP file
-        #[pallet::weight(300_000_000)]
-        pub fn burn(owner: OriginFor<T>, ipf_id: T::IpfId) -> DispatchResult {
-            IpfStorage::<T>::try_mutate(ipf_id, |ipf_info| -> DispatchResult {
-                let owner = ensure_signed(owner)?;
-                let t = ipf_info.take().ok_or(Error::<T>::IpfNotFound)?;
-                ensure!(t.owner == owner, Error::<T>::NoPermission);
+        /// Reserve the deposit for `len` bytes of metadata of `ipf_id` from `depositor`
+        fn reserve_metadata_deposit(
+            ipf_id: T::IpfId,
+            depositor: &T::AccountId,
+            len: usize,
+        ) -> DispatchResult {
+            let deposit = T::MetadataDepositBase::get().saturating_add(
+                T::MetadataDepositPerByte::get().saturating_mul((len as u32).into()),
+            );
 
-                IpfByOwner::<T>::remove(owner.clone(), ipf_id);
+            T::Currency::reserve(depositor, deposit)?;
+            MetadataDeposits::<T>::insert(ipf_id, (depositor.clone(), deposit));
 
-                Self::deposit_event(Event::Burned(owner, ipf_id));
+            Ok(())
+        }
 
-                Ok(())
-            })
+        /// Return the metadata deposit of `ipf_id` to the account that reserved it
+        fn release_metadata_deposit(ipf_id: T::IpfId) {
+            if let Some((depositor, deposit)) = MetadataDeposits::<T>::take(ipf_id) {
+                T::Currency::unreserve(&depositor, deposit);
+            }
         }
-    }
 
-    impl<T: Config> Pallet<T> {
+        /// Move an IPF from `owner` to `target`, unless it's locked
         pub fn send(owner: T::AccountId, ipf_id: T::IpfId, target: T::AccountId) -> DispatchResult {
             IpfStorage::<T>::try_mutate(ipf_id, |ipf_info| -> DispatchResult {
                 let t = ipf_info.take().ok_or(Error::<T>::IpfNotFound)?;
 
                 ensure!(t.owner == owner, Error::<T>::NoPermission);
+                ensure!(!IpfLocks::<T>::contains_key(ipf_id), Error::<T>::IpfLocked);
 
                 *ipf_info = Some(IpfInfo {
                     owner: target.clone(),
@@ -176,14 +736,153 @@ pub mod pallet {
                     data: t.data,
                 });
 
-                IpfByOwner::<T>::remove(owner, ipf_id);
-                IpfByOwner::<T>::insert(target, ipf_id, ());
+                IpfByOwner::<T>::remove(&owner, ipf_id);
+                IpfByOwner::<T>::insert(&target, ipf_id, ());
+
+                Self::deposit_event(Event::Transferred {
+                    from: owner,
+                    to: target,
+                    ipf_id,
+                });
+
+                Ok(())
+            })
+        }
+
+        /// Lock an IPF under `id` so it can't be burned or moved until `unlock` is called with the same `id`.
+        /// Locking an IPF again under the same `id` does nothing
+        pub fn lock(ipf_id: T::IpfId, id: LockIdentifier) -> DispatchResult {
+            ensure!(
+                IpfStorage::<T>::contains_key(ipf_id),
+                Error::<T>::IpfNotFound
+            );
+
+            IpfLocks::<T>::try_mutate(ipf_id, |lock| -> DispatchResult {
+                ensure!(lock.map_or(true, |l| l == id), Error::<T>::IpfLocked);
+
+                *lock = Some(id);
 
                 Ok(())
             })
         }
+
+        /// Release the lock `id` holds on an IPF, doing nothing if it isn't locked
+        pub fn unlock(ipf_id: T::IpfId, id: LockIdentifier) -> DispatchResult {
+            IpfLocks::<T>::try_mutate_exists(ipf_id, |lock| -> DispatchResult {
+                ensure!(lock.map_or(true, |l| l == id), Error::<T>::IpfLocked);
+
+                *lock = None;
+
+                Ok(())
+            })
+        }
+
+        /// Pay the authors of `ipf_id` their royalty on a sale or licensing worth `amount`, from `payer`.
+        /// Accepted co-authors get their share of the royalty and the author the rest.
+        /// Returns the royalty paid, for the caller to take off what goes to the seller
+        pub fn pay_royalty(
+            ipf_id: T::IpfId,
+            payer: &T::AccountId,
+            amount: BalanceOf<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            let info = IpfStorage::<T>::get(ipf_id).ok_or(Error::<T>::IpfNotFound)?;
+            let royalty = Royalties::<T>::get(ipf_id).unwrap_or_default() * amount;
+
+            let mut author_part = royalty;
+            let mut payments: Vec<(T::AccountId, BalanceOf<T>)> = Vec::new();
+
+            for co_author in CoAuthors::<T>::get(ipf_id) {
+                if co_author.accepted {
+                    let part = co_author.share * royalty;
+                    author_part = author_part.saturating_sub(part);
+                    payments.push((co_author.account, part));
+                }
+            }
+
+            payments.push((info.author, author_part));
+
+            let mut paid: BalanceOf<T> = Zero::zero();
+
+            for (recipient, part) in payments {
+                // Authors don't pay themselves
+                if part.is_zero() || recipient == *payer {
+                    continue;
+                }
+
+                T::Currency::transfer(payer, &recipient, part, ExistenceRequirement::KeepAlive)?;
+
+                paid = paid.saturating_add(part);
+
+                Self::deposit_event(Event::RoyaltyPaid {
+                    ipf_id,
+                    payer: payer.clone(),
+                    recipient,
+                    amount: part,
+                });
+            }
+
+            Ok(paid)
+        }
+
+        /// The first IPF holding the content of `ipf_id`, which is `ipf_id` itself unless it's a derivative
+        pub fn original_ipf(ipf_id: T::IpfId) -> Option<T::IpfId> {
+            let info = IpfStorage::<T>::get(ipf_id)?;
+
+            DerivativeOf::<T>::get(ipf_id).or_else(|| IpfByContent::<T>::get(info.data.into_v1()))
+        }
+
+        /// Index `data` as the content of `ipf_id`, applying `on_duplicate` if another IPF holds it
+        fn register_content(
+            ipf_id: T::IpfId,
+            data: &Cid,
+            on_duplicate: DuplicateContent,
+        ) -> DispatchResult {
+            let key = data.clone().into_v1();
+
+            match IpfByContent::<T>::get(&key) {
+                Some(original) if original != ipf_id => {
+                    ensure!(
+                        on_duplicate == DuplicateContent::Derivative,
+                        Error::<T>::DuplicateContent
+                    );
+
+                    DerivativeOf::<T>::insert(ipf_id, original);
+                }
+                _ => {
+                    IpfByContent::<T>::insert(key, ipf_id);
+                    DerivativeOf::<T>::remove(ipf_id);
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Drop `ipf_id` from the index of `data`
+        fn unregister_content(ipf_id: T::IpfId, data: &Cid) {
+            let key = data.clone().into_v1();
+
+            if IpfByContent::<T>::get(&key) == Some(ipf_id) {
+                IpfByContent::<T>::remove(key);
+            }
+
+            DerivativeOf::<T>::remove(ipf_id);
+        }
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            crate::migrations::migrate::<T>()
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<(), &'static str> {
+            crate::migrations::pre_upgrade::<T>()
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade() -> Result<(), &'static str> {
+            crate::migrations::post_upgrade::<T>()
+        }
+    }
 }
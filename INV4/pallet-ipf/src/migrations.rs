@@ -0,0 +1,129 @@
+use super::pallet::*;
+use frame_support::{
+    pallet_prelude::*,
+    traits::{Get, GetStorageVersion, StorageVersion},
+};
+
+/// Current storage version of the pallet
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+/// IPF data moves from a bare hash to a `Cid`, indexed in `IpfByContent`
+pub mod v1 {
+    use super::*;
+    use primitives::{
+        cid::{DAG_PB, SHA2_256},
+        Cid, CidVersion, IpfInfo,
+    };
+    use sp_std::{convert::TryInto, vec::Vec};
+
+    pub type OldIpfInfoOf<T> = IpfInfo<
+        <T as frame_system::Config>::AccountId,
+        <T as frame_system::Config>::Hash,
+        IpfMetadataOf<T>,
+    >;
+
+    /// Hashes were the sha2-256 digest of CIDv0s, which is all a CIDv0 holds
+    fn cid_from_hash<T: Config>(hash: <T as frame_system::Config>::Hash) -> Option<Cid> {
+        Some(Cid {
+            version: CidVersion::V0,
+            codec: DAG_PB,
+            hash: SHA2_256,
+            digest: hash.as_ref().to_vec().try_into().ok()?,
+        })
+    }
+
+    /// Turn the data hash of every IPF into a `Cid`.
+    /// IPFs sharing content are marked as derivatives of the one with the lowest ID
+    pub fn migrate<T: Config>() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() >= 1 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        let mut reads: u64 = 1;
+        let mut writes: u64 = 0;
+
+        let mut contents: Vec<(T::IpfId, Cid)> = Vec::new();
+        let mut dropped: Vec<(T::AccountId, T::IpfId)> = Vec::new();
+
+        IpfStorage::<T>::translate::<OldIpfInfoOf<T>, _>(|ipf_id, old| {
+            reads += 1;
+            writes += 1;
+
+            let data = match cid_from_hash::<T>(old.data) {
+                Some(data) => data,
+                None => {
+                    log::error!(
+                        target: "runtime::ipf",
+                        "IPF {:?} has a data hash that isn't a sha2-256 digest, removing it",
+                        ipf_id
+                    );
+
+                    dropped.push((old.owner, ipf_id));
+                    return None;
+                }
+            };
+            contents.push((ipf_id, data.clone().into_v1()));
+
+            Some(IpfInfo {
+                owner: old.owner,
+                author: old.author,
+                metadata: old.metadata,
+                data,
+            })
+        });
+
+        // Don't leave the owner index pointing at removed IPFs
+        for (owner, ipf_id) in dropped {
+            writes += 1;
+
+            IpfByOwner::<T>::remove(owner, ipf_id);
+        }
+
+        contents.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (ipf_id, key) in contents {
+            reads += 1;
+            writes += 1;
+
+            match IpfByContent::<T>::get(&key) {
+                Some(original) => DerivativeOf::<T>::insert(ipf_id, original),
+                None => IpfByContent::<T>::insert(key, ipf_id),
+            }
+        }
+
+        StorageVersion::new(1).put::<Pallet<T>>();
+        writes += 1;
+
+        T::DbWeight::get().reads_writes(reads, writes)
+    }
+}
+
+/// Run every migration the on-chain storage version hasn't gone through yet
+pub fn migrate<T: Config>() -> Weight {
+    v1::migrate::<T>()
+}
+
+/// The migrations only run from an older storage version
+#[cfg(feature = "try-runtime")]
+pub fn pre_upgrade<T: Config>() -> Result<(), &'static str> {
+    if Pallet::<T>::on_chain_storage_version() > STORAGE_VERSION {
+        return Err("On-chain storage version is newer than the pallet's");
+    }
+
+    Ok(())
+}
+
+/// Storage must be at the current version once the migrations ran
+#[cfg(feature = "try-runtime")]
+pub fn post_upgrade<T: Config>() -> Result<(), &'static str> {
+    if Pallet::<T>::on_chain_storage_version() != STORAGE_VERSION {
+        return Err("On-chain storage version wasn't updated");
+    }
+
+    // Entries left in the old shape are skipped when iterating values
+    if IpfStorage::<T>::iter_values().count() != IpfStorage::<T>::iter_keys().count() {
+        return Err("IpfStorage has undecodable entries");
+    }
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ use frame_support::{
     traits::{ConstU32, Contains},
 };
 use sp_core::H256;
-use sp_runtime::{testing::Header, traits::IdentityLookup};
+use sp_runtime::{testing::Header, traits::IdentityLookup, DispatchResult, Percent};
 use sp_std::convert::TryFrom;
 
 use super::*;
@@ -17,6 +17,7 @@ parameter_types! {
 }
 
 pub type AccountId = u128;
+pub type Balance = u128;
 pub type BlockNumber = u64;
 
 impl frame_system::Config for Runtime {
@@ -35,7 +36,7 @@ impl frame_system::Config for Runtime {
     type BlockLength = ();
     type Version = ();
     type PalletInfo = PalletInfo;
-    type AccountData = ();
+    type AccountData = pallet_balances::AccountData<Balance>;
     type OnNewAccount = ();
     type OnKilledAccount = ();
     type DbWeight = ();
@@ -47,15 +48,63 @@ impl frame_system::Config for Runtime {
 }
 
 parameter_types! {
+    pub const ExistentialDeposit: Balance = 1;
+}
+
+impl pallet_balances::Config for Runtime {
+    type MaxLocks = ();
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+}
+
+parameter_types! {
+    pub const MaxRoyalty: Percent = Percent::from_percent(50);
+    pub const MaxCoAuthors: u32 = 2;
     pub const MaxIpfMetadata: u32 = 32;
+    pub const MaxIpfVersions: u32 = 2;
+    pub const MaxIpfsPerBatch: u32 = 3;
+    pub static MetadataDepositBase: Balance = 0;
+    pub static MetadataDepositPerByte: Balance = 0;
+    pub static OnDuplicateContent: DuplicateContent = DuplicateContent::Derivative;
+    pub static CreatedIpSets: Vec<(AccountId, Vec<u64>)> = vec![];
 }
 
 impl Config for Runtime {
     type IpfId = u64;
     type MaxIpfMetadata = MaxIpfMetadata;
+    type MaxIpfVersions = MaxIpfVersions;
+    type OnDuplicateContent = OnDuplicateContent;
+    type Currency = Balances;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type MaxRoyalty = MaxRoyalty;
+    type MaxCoAuthors = MaxCoAuthors;
+    type MaxIpfsPerBatch = MaxIpfsPerBatch;
+    type IpsCreator = MockIpsCreator;
     type Event = Event;
 }
 
+/// Records the IP Sets `mint_batch` asks for in `CreatedIpSets`
+pub struct MockIpsCreator;
+impl IpsCreator<AccountId, <Runtime as Config>::IpfId> for MockIpsCreator {
+    type Params = ();
+
+    fn create_ips(
+        owner: AccountId,
+        ipfs: Vec<<Runtime as Config>::IpfId>,
+        _params: (),
+    ) -> DispatchResult {
+        CreatedIpSets::mutate(|created| created.push((owner, ipfs)));
+        Ok(())
+    }
+}
+
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
 type Block = frame_system::mocking::MockBlock<Runtime>;
 
@@ -67,6 +116,7 @@ impl Contains<Call> for BaseFilter {
             // Remark is used as a no-op call in the benchmarking
             Call::System(SystemCall::remark { .. }) => true,
             Call::System(_) => false,
+            Call::Balances(_) => true,
         }
     }
 }
@@ -78,25 +128,28 @@ construct_runtime!(
         UncheckedExtrinsic = UncheckedExtrinsic,
     {
         System: frame_system::{Pallet, Call, Storage, Config, Event<T>},
-        Ipf: ipf::{Pallet, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Ipf: ipf::{Pallet, Storage, Config<T>, Event<T>},
     }
 );
 
 pub const ALICE: AccountId = 1;
 pub const BOB: AccountId = 2;
+pub const CHARLIE: AccountId = 3;
 pub const IPF_ID: <Runtime as Config>::IpfId = 0;
 pub const IPF_ID_DOESNT_EXIST: <Runtime as Config>::IpfId = 100;
-pub const MOCK_DATA: [u8; 32] = [
-    12, 47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25, 12, 218,
-    0, 230, 247, 32, 73, 152, 66, 243, 27, 92, 95,
+pub const LOCKED_IPF_ID: <Runtime as Config>::IpfId = 1;
+pub const MOCK_DATA: [u8; 34] = [
+    18, 32, 12, 47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25,
+    12, 218, 0, 230, 247, 32, 73, 152, 66, 243, 27, 92, 95,
 ];
 pub const MOCK_METADATA: &'static [u8] = &[
     12, 47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25, 12, 218,
     0, 230, 247, 32, 73, 152, 66, 243, 27, 92, 95,
 ];
-pub const MOCK_DATA_SECONDARY: [u8; 32] = [
-    47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25, 12, 218, 0,
-    230, 247, 32, 73, 152, 66, 243, 27, 92, 95, 12,
+pub const MOCK_DATA_SECONDARY: [u8; 34] = [
+    18, 32, 47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25, 12,
+    218, 0, 230, 247, 32, 73, 152, 66, 243, 27, 92, 95, 12,
 ];
 pub const MOCK_METADATA_SECONDARY: &'static [u8] = &[
     47, 182, 72, 140, 51, 139, 219, 171, 74, 247, 18, 123, 28, 200, 236, 221, 85, 25, 12, 218, 0,
@@ -107,20 +160,37 @@ pub const MOCK_METADATA_PAST_MAX: &'static [u8] = &[
     0, 230, 247, 32, 73, 152, 66, 243, 27, 92, 95, 42,
 ];
 
-pub struct ExtBuilder;
+pub struct ExtBuilder {
+    ipfs: Vec<GenesisIpfData<Runtime>>,
+}
 
 impl Default for ExtBuilder {
     fn default() -> Self {
-        ExtBuilder
+        ExtBuilder { ipfs: Vec::new() }
     }
 }
 
 impl ExtBuilder {
+    pub fn ipfs(mut self, ipfs: Vec<GenesisIpfData<Runtime>>) -> Self {
+        self.ipfs = ipfs;
+        self
+    }
+
     pub fn build(self) -> sp_io::TestExternalities {
-        let t = frame_system::GenesisConfig::default()
+        let mut t = frame_system::GenesisConfig::default()
             .build_storage::<Runtime>()
             .unwrap();
 
+        pallet_balances::GenesisConfig::<Runtime> {
+            balances: vec![(ALICE, 1_000), (BOB, 1_000), (CHARLIE, 1_000)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        ipf::GenesisConfig::<Runtime> { ipfs: self.ipfs }
+            .assimilate_storage(&mut t)
+            .unwrap();
+
         let mut ext = sp_io::TestExternalities::new(t);
         ext.execute_with(|| System::set_block_number(1));
         ext
@@ -3,7 +3,7 @@
 use super::*;
 use frame_support::{assert_noop, assert_ok};
 use mock::*;
-use sp_core::H256;
+use primitives::{Cid, CidVersion, CoAuthor};
 use sp_runtime::DispatchError;
 
 #[test]
@@ -13,13 +13,13 @@ fn mint_should_work() {
         assert_ok!(Ipf::mint(
             Origin::signed(BOB),
             MOCK_METADATA.to_vec(),
-            H256::from(MOCK_DATA)
+            MOCK_DATA.to_vec()
         ));
         assert_eq!(Ipf::next_ipf_id(), 1);
         assert_ok!(Ipf::mint(
             Origin::signed(ALICE),
             MOCK_METADATA_SECONDARY.to_vec(),
-            H256::from(MOCK_DATA_SECONDARY)
+            MOCK_DATA_SECONDARY.to_vec()
         ));
         assert_eq!(Ipf::next_ipf_id(), 2);
 
@@ -29,7 +29,7 @@ fn mint_should_work() {
                 author: BOB,
                 owner: BOB,
                 metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA)
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
             })
         );
 
@@ -39,7 +39,7 @@ fn mint_should_work() {
                 author: ALICE,
                 owner: ALICE,
                 metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA_SECONDARY)
+                data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
             })
         );
     });
@@ -52,7 +52,7 @@ fn mint_should_fail() {
             Ipf::mint(
                 Origin::none(),
                 MOCK_METADATA_PAST_MAX.to_vec(),
-                H256::from(MOCK_DATA)
+                MOCK_DATA.to_vec()
             ),
             DispatchError::BadOrigin
         );
@@ -60,7 +60,7 @@ fn mint_should_fail() {
             Ipf::mint(
                 Origin::signed(BOB),
                 MOCK_METADATA_PAST_MAX.to_vec(),
-                H256::from(MOCK_DATA)
+                MOCK_DATA.to_vec()
             ),
             Error::<Runtime>::MaxMetadataExceeded,
         );
@@ -70,7 +70,7 @@ fn mint_should_fail() {
             Ipf::mint(
                 Origin::signed(BOB),
                 MOCK_METADATA.to_vec(),
-                H256::from(MOCK_DATA)
+                MOCK_DATA.to_vec()
             ),
             Error::<Runtime>::NoAvailableIpfId
         );
@@ -79,18 +79,102 @@ fn mint_should_fail() {
     });
 }
 
+#[test]
+fn mint_batch_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(ALICE),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_ok!(Ipf::mint_batch(
+            Origin::signed(BOB),
+            vec![
+                (MOCK_METADATA.to_vec(), MOCK_DATA.to_vec()),
+                (
+                    MOCK_METADATA_SECONDARY.to_vec(),
+                    MOCK_DATA_SECONDARY.to_vec()
+                ),
+            ]
+            .try_into()
+            .unwrap(),
+            None
+        ));
+        assert_eq!(Ipf::next_ipf_id(), 3);
+        assert_eq!(Ipf::ipf_storage(1).unwrap().owner, BOB);
+        assert_eq!(
+            Ipf::ipf_storage(2).unwrap().data,
+            Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
+        );
+        assert!(CreatedIpSets::get().is_empty());
+
+        assert_ok!(Ipf::mint_batch(
+            Origin::signed(CHARLIE),
+            vec![
+                (MOCK_METADATA.to_vec(), MOCK_DATA_SECONDARY.to_vec()),
+                (MOCK_METADATA.to_vec(), MOCK_DATA.to_vec()),
+            ]
+            .try_into()
+            .unwrap(),
+            Some(())
+        ));
+        assert_eq!(Ipf::next_ipf_id(), 5);
+        assert_eq!(CreatedIpSets::get(), vec![(CHARLIE, vec![3, 4])]);
+    });
+}
+
+#[test]
+fn mint_batch_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_noop!(
+            Ipf::mint_batch(
+                Origin::none(),
+                vec![(MOCK_METADATA.to_vec(), MOCK_DATA.to_vec())]
+                    .try_into()
+                    .unwrap(),
+                None
+            ),
+            DispatchError::BadOrigin
+        );
+
+        // An invalid entry reverts the IPFs minted before it
+        assert_noop!(
+            Ipf::mint_batch(
+                Origin::signed(BOB),
+                vec![
+                    (MOCK_METADATA.to_vec(), MOCK_DATA.to_vec()),
+                    (MOCK_METADATA_PAST_MAX.to_vec(), MOCK_DATA.to_vec()),
+                ]
+                .try_into()
+                .unwrap(),
+                Some(())
+            ),
+            Error::<Runtime>::MaxMetadataExceeded
+        );
+
+        assert_eq!(Ipf::next_ipf_id(), 0);
+        assert!(CreatedIpSets::get().is_empty());
+    });
+}
+
 #[test]
 fn burn_should_work() {
     ExtBuilder::default().build().execute_with(|| {
         assert_ok!(Ipf::mint(
             Origin::signed(BOB),
             MOCK_METADATA.to_vec(),
-            H256::from(MOCK_DATA)
+            MOCK_DATA.to_vec()
         ));
 
         assert_ok!(Ipf::burn(Origin::signed(BOB), IPF_ID));
 
         assert_eq!(IpfStorage::<Runtime>::get(0), None);
+
+        System::assert_last_event(mock::Event::Ipf(crate::Event::Burned {
+            owner: BOB,
+            ipf_id: IPF_ID,
+        }));
     });
 }
 
@@ -100,7 +184,7 @@ fn burn_should_fail() {
         assert_ok!(Ipf::mint(
             Origin::signed(BOB),
             MOCK_METADATA.to_vec(),
-            H256::from(MOCK_DATA)
+            MOCK_DATA.to_vec()
         ));
 
         assert_noop!(Ipf::burn(Origin::none(), IPF_ID), DispatchError::BadOrigin);
@@ -121,7 +205,7 @@ fn burn_should_fail() {
                 author: BOB,
                 owner: BOB,
                 metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA)
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
             })
         );
     });
@@ -134,13 +218,13 @@ fn send_should_work() {
         assert_ok!(Ipf::mint(
             Origin::signed(BOB),
             MOCK_METADATA.to_vec(),
-            H256::from(MOCK_DATA)
+            MOCK_DATA.to_vec()
         ));
         assert_eq!(Ipf::next_ipf_id(), 1);
         assert_ok!(Ipf::mint(
             Origin::signed(ALICE),
             MOCK_METADATA_SECONDARY.to_vec(),
-            H256::from(MOCK_DATA_SECONDARY)
+            MOCK_DATA_SECONDARY.to_vec()
         ));
         assert_eq!(Ipf::next_ipf_id(), 2);
 
@@ -150,19 +234,25 @@ fn send_should_work() {
                 author: BOB,
                 owner: BOB,
                 metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA)
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
             })
         );
 
         assert_ok!(Ipf::send(BOB, 0, ALICE));
 
+        System::assert_last_event(mock::Event::Ipf(crate::Event::Transferred {
+            from: BOB,
+            to: ALICE,
+            ipf_id: 0,
+        }));
+
         assert_eq!(
             IpfStorage::<Runtime>::get(0),
             Some(IpfInfoOf::<Runtime> {
                 author: BOB,
                 owner: ALICE,
                 metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA)
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
             })
         );
 
@@ -172,7 +262,7 @@ fn send_should_work() {
                 author: ALICE,
                 owner: ALICE,
                 metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA_SECONDARY)
+                data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
             })
         );
 
@@ -184,7 +274,7 @@ fn send_should_work() {
                 author: ALICE,
                 owner: BOB,
                 metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA_SECONDARY)
+                data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
             })
         );
     });
@@ -197,13 +287,13 @@ fn send_should_fail() {
         assert_ok!(Ipf::mint(
             Origin::signed(BOB),
             MOCK_METADATA.to_vec(),
-            H256::from(MOCK_DATA)
+            MOCK_DATA.to_vec()
         ));
         assert_eq!(Ipf::next_ipf_id(), 1);
         assert_ok!(Ipf::mint(
             Origin::signed(ALICE),
             MOCK_METADATA_SECONDARY.to_vec(),
-            H256::from(MOCK_DATA_SECONDARY)
+            MOCK_DATA_SECONDARY.to_vec()
         ));
         assert_eq!(Ipf::next_ipf_id(), 2);
 
@@ -213,7 +303,7 @@ fn send_should_fail() {
                 author: BOB,
                 owner: BOB,
                 metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA)
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
             })
         );
 
@@ -223,7 +313,7 @@ fn send_should_fail() {
                 author: ALICE,
                 owner: ALICE,
                 metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA_SECONDARY)
+                data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
             })
         );
 
@@ -239,7 +329,7 @@ fn send_should_fail() {
                 author: BOB,
                 owner: BOB,
                 metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA)
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
             })
         );
 
@@ -249,8 +339,809 @@ fn send_should_fail() {
                 author: ALICE,
                 owner: ALICE,
                 metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
-                data: H256::from(MOCK_DATA_SECONDARY)
+                data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
+            })
+        );
+    });
+}
+
+#[test]
+fn metadata_deposit_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        MetadataDepositBase::set(10);
+        MetadataDepositPerByte::set(2);
+
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_eq!(Balances::reserved_balance(BOB), 74);
+        assert_eq!(Ipf::metadata_deposit(IPF_ID), Some((BOB, 74)));
+
+        // The editor pays for the new metadata
+        assert_ok!(Ipf::transfer(Origin::signed(BOB), IPF_ID, CHARLIE));
+        assert_ok!(Ipf::amend(
+            Origin::signed(CHARLIE),
+            IPF_ID,
+            MOCK_METADATA[..8].to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+        assert_eq!(Balances::reserved_balance(BOB), 0);
+        assert_eq!(Balances::reserved_balance(CHARLIE), 26);
+
+        assert_ok!(Ipf::burn(Origin::signed(CHARLIE), IPF_ID));
+        assert_eq!(Balances::reserved_balance(CHARLIE), 0);
+        assert_eq!(Ipf::metadata_deposit(IPF_ID), None);
+
+        MetadataDepositPerByte::set(100);
+        assert_noop!(
+            Ipf::mint(
+                Origin::signed(BOB),
+                MOCK_METADATA.to_vec(),
+                MOCK_DATA.to_vec()
+            ),
+            pallet_balances::Error::<Runtime>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn amend_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        System::set_block_number(2);
+
+        assert_ok!(Ipf::amend(
+            Origin::signed(BOB),
+            0,
+            MOCK_METADATA_SECONDARY.to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+
+        assert_eq!(
+            IpfStorage::<Runtime>::get(0),
+            Some(IpfInfoOf::<Runtime> {
+                author: BOB,
+                owner: BOB,
+                metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
+                data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
             })
         );
+
+        assert_eq!(
+            Ipf::ipf_versions(0).into_inner(),
+            vec![IpfVersionOf::<Runtime> {
+                data: Cid::from_bytes(&MOCK_DATA).unwrap(),
+                amended_at: 2,
+                editor: BOB,
+            }]
+        );
+
+        System::assert_has_event(mock::Event::Ipf(crate::Event::MetadataUpdated {
+            ipf_id: 0,
+            metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
+        }));
+        System::assert_last_event(mock::Event::Ipf(crate::Event::Amended {
+            editor: BOB,
+            ipf_id: 0,
+            data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap(),
+        }));
+
+        // Amending only the metadata doesn't make a new version
+        assert_ok!(Ipf::amend(
+            Origin::signed(BOB),
+            0,
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+
+        assert_eq!(Ipf::ipf_versions(0).len(), 1);
+        System::assert_last_event(mock::Event::Ipf(crate::Event::MetadataUpdated {
+            ipf_id: 0,
+            metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
+        }));
+
+        // Only the latest `MaxIpfVersions` versions are kept
+        assert_ok!(Ipf::amend(
+            Origin::signed(BOB),
+            0,
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::amend(
+            Origin::signed(BOB),
+            0,
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+
+        assert_eq!(
+            Ipf::ipf_versions(0)
+                .into_iter()
+                .map(|version| version.data)
+                .collect::<Vec<Cid>>(),
+            vec![
+                Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap(),
+                Cid::from_bytes(&MOCK_DATA).unwrap()
+            ]
+        );
+
+        assert_ok!(Ipf::burn(Origin::signed(BOB), 0));
+        assert!(Ipf::ipf_versions(0).is_empty());
+    });
+}
+
+#[test]
+fn amend_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_noop!(
+            Ipf::amend(
+                Origin::none(),
+                0,
+                MOCK_METADATA_SECONDARY.to_vec(),
+                MOCK_DATA_SECONDARY.to_vec()
+            ),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Ipf::amend(
+                Origin::signed(BOB),
+                IPF_ID_DOESNT_EXIST,
+                MOCK_METADATA_SECONDARY.to_vec(),
+                MOCK_DATA_SECONDARY.to_vec()
+            ),
+            Error::<Runtime>::IpfNotFound
+        );
+        assert_noop!(
+            Ipf::amend(
+                Origin::signed(ALICE),
+                0,
+                MOCK_METADATA_SECONDARY.to_vec(),
+                MOCK_DATA_SECONDARY.to_vec()
+            ),
+            Error::<Runtime>::NoPermission
+        );
+        assert_noop!(
+            Ipf::amend(
+                Origin::signed(BOB),
+                0,
+                MOCK_METADATA_PAST_MAX.to_vec(),
+                MOCK_DATA_SECONDARY.to_vec()
+            ),
+            Error::<Runtime>::MaxMetadataExceeded
+        );
+
+        assert!(Ipf::ipf_versions(0).is_empty());
+    });
+}
+
+#[test]
+fn transfer_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_ok!(Ipf::transfer(Origin::signed(BOB), 0, ALICE));
+
+        assert_eq!(
+            IpfStorage::<Runtime>::get(0),
+            Some(IpfInfoOf::<Runtime> {
+                author: BOB,
+                owner: ALICE,
+                metadata: MOCK_METADATA.to_vec().try_into().unwrap(),
+                data: Cid::from_bytes(&MOCK_DATA).unwrap()
+            })
+        );
+        assert_eq!(Ipf::ipf_by_owner(BOB, 0), None);
+        assert_eq!(Ipf::ipf_by_owner(ALICE, 0), Some(()));
+
+        System::assert_last_event(mock::Event::Ipf(crate::Event::Transferred {
+            from: BOB,
+            to: ALICE,
+            ipf_id: 0,
+        }));
+    });
+}
+
+#[test]
+fn transfer_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA_SECONDARY.to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+
+        assert_noop!(
+            Ipf::transfer(Origin::none(), 0, ALICE),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Ipf::transfer(Origin::signed(BOB), IPF_ID_DOESNT_EXIST, ALICE),
+            Error::<Runtime>::IpfNotFound
+        );
+        assert_noop!(
+            Ipf::transfer(Origin::signed(ALICE), 0, BOB),
+            Error::<Runtime>::NoPermission
+        );
+
+        assert_ok!(Ipf::lock(LOCKED_IPF_ID, *b"testlock"));
+        assert_noop!(
+            Ipf::transfer(Origin::signed(BOB), LOCKED_IPF_ID, ALICE),
+            Error::<Runtime>::IpfLocked
+        );
+
+        assert_eq!(Ipf::ipf_by_owner(BOB, 0), Some(()));
+        assert_eq!(Ipf::ipf_by_owner(BOB, LOCKED_IPF_ID), Some(()));
+    });
+}
+
+#[test]
+fn mint_should_parse_cid() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        let cid_v0 = Ipf::ipf_storage(0).unwrap().data;
+        assert_eq!(cid_v0.version, CidVersion::V0);
+        assert_eq!(cid_v0.codec, primitives::cid::DAG_PB);
+        assert_eq!(cid_v0.hash, primitives::cid::SHA2_256);
+        assert_eq!(&cid_v0.digest[..], &MOCK_DATA[2..]);
+        assert_eq!(cid_v0.to_bytes(), MOCK_DATA.to_vec());
+
+        // CIDv1 of raw (0x55) content
+        let mut cid_v1_bytes = vec![1, 0x55];
+        cid_v1_bytes.extend_from_slice(&MOCK_DATA);
+
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            cid_v1_bytes.clone()
+        ));
+
+        let cid_v1 = Ipf::ipf_storage(1).unwrap().data;
+        assert_eq!(cid_v1.version, CidVersion::V1);
+        assert_eq!(cid_v1.codec, 0x55);
+        assert_eq!(cid_v1.hash, primitives::cid::SHA2_256);
+        assert_eq!(cid_v1.to_bytes(), cid_v1_bytes);
+
+        System::assert_last_event(mock::Event::Ipf(crate::Event::Minted {
+            owner: BOB,
+            ipf_id: 1,
+            data: cid_v1,
+        }));
+
+        // Not a CID
+        assert_noop!(
+            Ipf::mint(
+                Origin::signed(BOB),
+                MOCK_METADATA.to_vec(),
+                MOCK_DATA[2..].to_vec()
+            ),
+            Error::<Runtime>::InvalidCid
+        );
+
+        // sha2-256 digest that is too short
+        assert_noop!(
+            Ipf::mint(
+                Origin::signed(BOB),
+                MOCK_METADATA.to_vec(),
+                vec![1, 0x55, 0x12, 0x20, 1, 2, 3]
+            ),
+            Error::<Runtime>::InvalidCid
+        );
+
+        // Trailing bytes after the digest
+        let mut trailing = cid_v1_bytes;
+        trailing.push(0);
+        assert_noop!(
+            Ipf::mint(Origin::signed(BOB), MOCK_METADATA.to_vec(), trailing),
+            Error::<Runtime>::InvalidCid
+        );
+    });
+}
+
+#[test]
+fn duplicate_content_should_be_derivative() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        // Same content as a CIDv1
+        let mut cid_v1_bytes = vec![1, 0x70];
+        cid_v1_bytes.extend_from_slice(&MOCK_DATA);
+
+        assert_ok!(Ipf::mint(
+            Origin::signed(ALICE),
+            MOCK_METADATA.to_vec(),
+            cid_v1_bytes
+        ));
+
+        assert_eq!(
+            Ipf::ipf_by_content(Cid::from_bytes(&MOCK_DATA).unwrap().into_v1()),
+            Some(0)
+        );
+        assert_eq!(Ipf::derivative_of(1), Some(0));
+        assert_eq!(Ipf::original_ipf(0), Some(0));
+        assert_eq!(Ipf::original_ipf(1), Some(0));
+
+        // Amending to new content makes it an original
+        assert_ok!(Ipf::amend(
+            Origin::signed(ALICE),
+            1,
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+
+        assert_eq!(Ipf::derivative_of(1), None);
+        assert_eq!(Ipf::original_ipf(1), Some(1));
+
+        assert_ok!(Ipf::burn(Origin::signed(BOB), 0));
+        assert_eq!(
+            Ipf::ipf_by_content(Cid::from_bytes(&MOCK_DATA).unwrap().into_v1()),
+            None
+        );
+        assert_eq!(Ipf::original_ipf(0), None);
+    });
+}
+
+#[test]
+fn duplicate_content_should_be_rejected() {
+    ExtBuilder::default().build().execute_with(|| {
+        OnDuplicateContent::set(DuplicateContent::Reject);
+
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA_SECONDARY.to_vec()
+        ));
+
+        assert_noop!(
+            Ipf::mint(
+                Origin::signed(ALICE),
+                MOCK_METADATA.to_vec(),
+                MOCK_DATA.to_vec()
+            ),
+            Error::<Runtime>::DuplicateContent
+        );
+        assert_noop!(
+            Ipf::amend(
+                Origin::signed(BOB),
+                1,
+                MOCK_METADATA.to_vec(),
+                MOCK_DATA.to_vec()
+            ),
+            Error::<Runtime>::DuplicateContent
+        );
+
+        // Re-amending an IPF to its own content is fine
+        assert_ok!(Ipf::amend(
+            Origin::signed(BOB),
+            0,
+            MOCK_METADATA_SECONDARY.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_ok!(Ipf::burn(Origin::signed(BOB), 0));
+        assert_ok!(Ipf::mint(
+            Origin::signed(ALICE),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_eq!(
+            Ipf::ipf_by_content(Cid::from_bytes(&MOCK_DATA).unwrap().into_v1()),
+            Some(2)
+        );
+    });
+}
+
+#[test]
+fn royalty_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::transfer(Origin::signed(BOB), 0, ALICE));
+
+        assert_ok!(Ipf::set_royalty(
+            Origin::signed(BOB),
+            0,
+            Percent::from_percent(10)
+        ));
+        assert_eq!(Ipf::royalty(0), Some(Percent::from_percent(10)));
+
+        assert_eq!(Ipf::pay_royalty(0, &CHARLIE, 200), Ok(20));
+        assert_eq!(Balances::free_balance(CHARLIE), 980);
+        assert_eq!(Balances::free_balance(BOB), 1_020);
+
+        System::assert_last_event(mock::Event::Ipf(crate::Event::RoyaltyPaid {
+            ipf_id: 0,
+            payer: CHARLIE,
+            recipient: BOB,
+            amount: 20,
+        }));
+
+        // The author doesn't pay themselves
+        assert_eq!(Ipf::pay_royalty(0, &BOB, 200), Ok(0));
+
+        assert_ok!(Ipf::set_royalty(
+            Origin::signed(BOB),
+            0,
+            Percent::from_percent(0)
+        ));
+        assert_eq!(Ipf::royalty(0), None);
+        assert_eq!(Ipf::pay_royalty(0, &CHARLIE, 200), Ok(0));
+    });
+}
+
+#[test]
+fn royalty_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::transfer(Origin::signed(BOB), 0, ALICE));
+
+        // The owner isn't the author
+        assert_noop!(
+            Ipf::set_royalty(Origin::signed(ALICE), 0, Percent::from_percent(10)),
+            Error::<Runtime>::NotAuthor
+        );
+        assert_noop!(
+            Ipf::set_royalty(Origin::signed(BOB), 0, Percent::from_percent(51)),
+            Error::<Runtime>::RoyaltyTooHigh
+        );
+        assert_noop!(
+            Ipf::set_royalty(
+                Origin::signed(BOB),
+                IPF_ID_DOESNT_EXIST,
+                Percent::from_percent(10)
+            ),
+            Error::<Runtime>::IpfNotFound
+        );
+
+        assert_ok!(Ipf::set_royalty(
+            Origin::signed(BOB),
+            0,
+            Percent::from_percent(50)
+        ));
+        assert!(Ipf::pay_royalty(0, &CHARLIE, 2_000).is_err());
+        assert_eq!(Balances::free_balance(CHARLIE), 1_000);
+    });
+}
+
+#[test]
+fn transfer_authorship_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::set_royalty(
+            Origin::signed(BOB),
+            0,
+            Percent::from_percent(10)
+        ));
+
+        assert_noop!(
+            Ipf::transfer_authorship(Origin::signed(ALICE), 0, ALICE),
+            Error::<Runtime>::NotAuthor
+        );
+
+        assert_ok!(Ipf::transfer_authorship(Origin::signed(BOB), 0, ALICE));
+
+        let info = Ipf::ipf_storage(0).unwrap();
+        assert_eq!(info.author, ALICE);
+        assert_eq!(info.owner, BOB);
+
+        System::assert_last_event(mock::Event::Ipf(crate::Event::AuthorshipTransferred {
+            ipf_id: 0,
+            from: BOB,
+            to: ALICE,
+        }));
+
+        assert_eq!(Ipf::pay_royalty(0, &CHARLIE, 100), Ok(10));
+        assert_eq!(Balances::free_balance(ALICE), 1_010);
+
+        assert_noop!(
+            Ipf::set_royalty(Origin::signed(BOB), 0, Percent::from_percent(20)),
+            Error::<Runtime>::NotAuthor
+        );
+    });
+}
+
+#[test]
+fn transfer_authorship_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_noop!(
+            Ipf::transfer_authorship(Origin::none(), 0, ALICE),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Ipf::transfer_authorship(Origin::signed(BOB), IPF_ID_DOESNT_EXIST, ALICE),
+            Error::<Runtime>::IpfNotFound
+        );
+
+        assert_ok!(Ipf::invite_co_author(
+            Origin::signed(BOB),
+            0,
+            ALICE,
+            Percent::from_percent(10)
+        ));
+
+        // Neither invited nor accepted co-authors can become the author
+        assert_noop!(
+            Ipf::transfer_authorship(Origin::signed(BOB), 0, ALICE),
+            Error::<Runtime>::AlreadyCoAuthor
+        );
+
+        assert_ok!(Ipf::accept_co_authorship(Origin::signed(ALICE), 0));
+
+        assert_noop!(
+            Ipf::transfer_authorship(Origin::signed(BOB), 0, ALICE),
+            Error::<Runtime>::AlreadyCoAuthor
+        );
+
+        assert_eq!(Ipf::ipf_storage(0).unwrap().author, BOB);
+    });
+}
+
+#[test]
+fn co_authors_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+        assert_ok!(Ipf::set_royalty(
+            Origin::signed(BOB),
+            0,
+            Percent::from_percent(50)
+        ));
+
+        assert_ok!(Ipf::invite_co_author(
+            Origin::signed(BOB),
+            0,
+            ALICE,
+            Percent::from_percent(40)
+        ));
+        System::assert_last_event(mock::Event::Ipf(crate::Event::CoAuthorInvited {
+            ipf_id: 0,
+            co_author: ALICE,
+            share: Percent::from_percent(40),
+        }));
+
+        // Pending co-authors receive nothing
+        assert_eq!(Ipf::pay_royalty(0, &CHARLIE, 200), Ok(100));
+        assert_eq!(Balances::free_balance(BOB), 1_100);
+        assert_eq!(Balances::free_balance(ALICE), 1_000);
+
+        assert_ok!(Ipf::accept_co_authorship(Origin::signed(ALICE), 0));
+        System::assert_last_event(mock::Event::Ipf(crate::Event::CoAuthorAccepted {
+            ipf_id: 0,
+            co_author: ALICE,
+            share: Percent::from_percent(40),
+        }));
+
+        assert_eq!(
+            Ipf::co_authors(0).into_inner(),
+            vec![CoAuthor {
+                account: ALICE,
+                share: Percent::from_percent(40),
+                accepted: true
+            }]
+        );
+
+        assert_eq!(Ipf::pay_royalty(0, &CHARLIE, 200), Ok(100));
+        assert_eq!(Balances::free_balance(ALICE), 1_040);
+        assert_eq!(Balances::free_balance(BOB), 1_160);
+
+        // Co-authors can leave on their own
+        assert_ok!(Ipf::remove_co_author(Origin::signed(ALICE), 0, ALICE));
+        System::assert_last_event(mock::Event::Ipf(crate::Event::CoAuthorRemoved {
+            ipf_id: 0,
+            co_author: ALICE,
+        }));
+        assert!(Ipf::co_authors(0).is_empty());
+    });
+}
+
+#[test]
+fn co_authors_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_noop!(
+            Ipf::invite_co_author(Origin::signed(ALICE), 0, CHARLIE, Percent::from_percent(10)),
+            Error::<Runtime>::NotAuthor
+        );
+        assert_noop!(
+            Ipf::invite_co_author(Origin::signed(BOB), 0, BOB, Percent::from_percent(10)),
+            Error::<Runtime>::AlreadyCoAuthor
+        );
+
+        assert_ok!(Ipf::invite_co_author(
+            Origin::signed(BOB),
+            0,
+            ALICE,
+            Percent::from_percent(60)
+        ));
+
+        assert_noop!(
+            Ipf::invite_co_author(Origin::signed(BOB), 0, ALICE, Percent::from_percent(10)),
+            Error::<Runtime>::AlreadyCoAuthor
+        );
+        assert_noop!(
+            Ipf::invite_co_author(Origin::signed(BOB), 0, CHARLIE, Percent::from_percent(41)),
+            Error::<Runtime>::SharesExceeded
+        );
+
+        assert_ok!(Ipf::invite_co_author(
+            Origin::signed(BOB),
+            0,
+            CHARLIE,
+            Percent::from_percent(40)
+        ));
+        assert_noop!(
+            Ipf::invite_co_author(Origin::signed(BOB), 0, 4, Percent::from_percent(0)),
+            Error::<Runtime>::TooManyCoAuthors
+        );
+
+        assert_noop!(
+            Ipf::accept_co_authorship(Origin::signed(4), 0),
+            Error::<Runtime>::NotCoAuthor
+        );
+        assert_ok!(Ipf::accept_co_authorship(Origin::signed(ALICE), 0));
+        assert_noop!(
+            Ipf::accept_co_authorship(Origin::signed(ALICE), 0),
+            Error::<Runtime>::NotCoAuthor
+        );
+
+        // Only the author or the co-author can remove them
+        assert_noop!(
+            Ipf::remove_co_author(Origin::signed(CHARLIE), 0, ALICE),
+            Error::<Runtime>::NoPermission
+        );
+        assert_ok!(Ipf::remove_co_author(Origin::signed(BOB), 0, CHARLIE));
+        assert_noop!(
+            Ipf::remove_co_author(Origin::signed(BOB), 0, CHARLIE),
+            Error::<Runtime>::NotCoAuthor
+        );
+
+        assert_ok!(Ipf::burn(Origin::signed(BOB), 0));
+        assert!(Ipf::co_authors(0).is_empty());
+    });
+}
+
+#[test]
+fn genesis_should_work() {
+    ExtBuilder::default()
+        .ipfs(vec![
+            (BOB, MOCK_METADATA.to_vec(), MOCK_DATA.to_vec()),
+            (
+                ALICE,
+                MOCK_METADATA_SECONDARY.to_vec(),
+                MOCK_DATA_SECONDARY.to_vec(),
+            ),
+        ])
+        .build()
+        .execute_with(|| {
+            assert_eq!(Ipf::next_ipf_id(), 2);
+
+            assert_eq!(
+                IpfStorage::<Runtime>::get(1),
+                Some(IpfInfoOf::<Runtime> {
+                    author: ALICE,
+                    owner: ALICE,
+                    metadata: MOCK_METADATA_SECONDARY.to_vec().try_into().unwrap(),
+                    data: Cid::from_bytes(&MOCK_DATA_SECONDARY).unwrap()
+                })
+            );
+            assert_eq!(Ipf::ipf_by_owner(BOB, 0), Some(()));
+            assert_eq!(Ipf::ipf_by_owner(ALICE, 1), Some(()));
+
+            // No metadata deposit is taken at genesis
+            assert_eq!(Ipf::metadata_deposit(0), None);
+            assert_eq!(Balances::reserved_balance(BOB), 0);
+            assert_eq!(
+                Ipf::ipf_by_content(Cid::from_bytes(&MOCK_DATA).unwrap().into_v1()),
+                Some(0)
+            );
+
+            assert_ok!(Ipf::mint(
+                Origin::signed(BOB),
+                MOCK_METADATA.to_vec(),
+                MOCK_DATA.to_vec()
+            ));
+            assert_eq!(Ipf::next_ipf_id(), 3);
+            assert_eq!(Ipf::derivative_of(2), Some(0));
+        });
+}
+
+#[test]
+fn lock_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        const LOCK_ID: [u8; 8] = *b"testlock";
+
+        assert_ok!(Ipf::mint(
+            Origin::signed(BOB),
+            MOCK_METADATA.to_vec(),
+            MOCK_DATA.to_vec()
+        ));
+
+        assert_noop!(
+            Ipf::lock(IPF_ID_DOESNT_EXIST, LOCK_ID),
+            Error::<Runtime>::IpfNotFound
+        );
+
+        assert_ok!(Ipf::lock(0, LOCK_ID));
+        assert_ok!(Ipf::lock(0, LOCK_ID));
+        assert_eq!(Ipf::locked_by(0), Some(LOCK_ID));
+
+        assert_noop!(Ipf::lock(0, *b"otherloc"), Error::<Runtime>::IpfLocked);
+        assert_noop!(Ipf::unlock(0, *b"otherloc"), Error::<Runtime>::IpfLocked);
+
+        assert_noop!(
+            Ipf::burn(Origin::signed(BOB), 0),
+            Error::<Runtime>::IpfLocked
+        );
+        assert_noop!(
+            Ipf::transfer(Origin::signed(BOB), 0, ALICE),
+            Error::<Runtime>::IpfLocked
+        );
+        assert_noop!(Ipf::send(BOB, 0, ALICE), Error::<Runtime>::IpfLocked);
+
+        assert_ok!(Ipf::unlock(0, LOCK_ID));
+        assert_eq!(Ipf::locked_by(0), None);
+
+        assert_ok!(Ipf::send(BOB, 0, ALICE));
+        assert_ok!(Ipf::burn(Origin::signed(ALICE), 0));
     });
 }
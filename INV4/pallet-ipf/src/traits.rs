@@ -0,0 +1,20 @@
+use frame_support::Parameter;
+use sp_runtime::{DispatchError, DispatchResult};
+use sp_std::vec::Vec;
+
+/// Creates an IP Set holding IPFs minted with `mint_batch`, implemented by `pallet-inv4`
+pub trait IpsCreator<AccountId, IpfId> {
+    /// Settings of the IP Set, passed to `mint_batch`
+    type Params: Parameter;
+
+    /// Create an IP Set owned by `owner` containing `ipfs`, which `owner` owns
+    fn create_ips(owner: AccountId, ipfs: Vec<IpfId>, params: Self::Params) -> DispatchResult;
+}
+
+impl<AccountId, IpfId> IpsCreator<AccountId, IpfId> for () {
+    type Params = ();
+
+    fn create_ips(_owner: AccountId, _ipfs: Vec<IpfId>, _params: ()) -> DispatchResult {
+        Err(DispatchError::Other("IP Set creation is not supported"))
+    }
+}
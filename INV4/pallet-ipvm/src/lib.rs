@@ -1,55 +1,54 @@
+//! # Pallet IPVM
+//! IP Virtual Machine
+//!
+//! - [`Config`]
+//! - [`Call`]
+//! - [`Pallet`]
+//!
+//! ## Overview
+//! This pallet creates IP Sets backed by a smart contract from `pallet-contracts`.
+//!
+//! ### Pallet Functions
+//!
+//! `create` - Create an IP Set holding the code of a smart contract, instantiated from the IP Set account
+
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::unused_unit)]
 #![allow(clippy::too_many_arguments)]
 
-use frame_support::{pallet_prelude::*, traits::Currency as FSCurrency};
+use frame_support::{
+    pallet_prelude::*,
+    traits::{Currency, ExistenceRequirement},
+};
 use frame_system::pallet_prelude::*;
 use sp_std::vec::Vec;
 
-//#[cfg(test)]
-//mod mock;
-//#[cfg(test)]
-//mod tests;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
 pub use pallet::*;
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
-    use primitives::OneOrPercent;
+    use inv4::{util::derive_ips_account, AnyId};
+    use primitives::{
+        cid::{RAW, SHA2_256},
+        Cid, CidVersion, OneOrPercent,
+    };
     use sp_core::crypto::UncheckedFrom;
-    use sp_runtime::traits::{CheckedAdd, Hash, StaticLookup};
-    use sp_std::vec;
+    use sp_runtime::traits::CheckedAdd;
+    use sp_std::{convert::TryInto, vec};
 
     #[pallet::config]
-    pub trait Config:
-        frame_system::Config
-        + ips::Config
-        + ipf::Config
-        + ipl::Config
-        + pallet_contracts::Config
-        + pallet_balances::Config
-    {
-        /// The IPS Pallet Events
+    pub trait Config: frame_system::Config + inv4::Config + pallet_contracts::Config {
+        /// The IPVM Pallet Events
         type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
-        /// Currency
-        type Currency: FSCurrency<Self::AccountId>;
-
-        /// The minimum amount required to keep an account open.
-        #[pallet::constant]
-        type ExistentialDeposit: Get<
-            <<Self as Config>::Currency as FSCurrency<<Self as frame_system::Config>::AccountId>>::Balance,
-        >;
     }
 
-    pub type BalanceOf<T> =
-        <<T as Config>::Currency as FSCurrency<<T as frame_system::Config>::AccountId>>::Balance;
-
-    pub type ContractsBalanceOf<T> = <<T as pallet_contracts::Config>::Currency as FSCurrency<
-        <T as frame_system::Config>::AccountId,
-    >>::Balance;
-
-    pub type BalancesBalanceOf<T> = <<T as pallet_contracts::Config>::Currency as FSCurrency<
+    pub type BalanceOf<T> = <<T as pallet_contracts::Config>::Currency as Currency<
         <T as frame_system::Config>::AccountId,
     >>::Balance;
 
@@ -59,13 +58,22 @@ pub mod pallet {
     #[pallet::event]
     #[pallet::generate_deposit(fn deposit_event)]
     pub enum Event<T: Config> {
-        Created(T::AccountId, T::IpsId),
+        /// An IP Set backed by a smart contract was created
+        Created {
+            ips_account: T::AccountId,
+            ips_id: <T as inv4::Config>::IpId,
+            ipf_id: <T as ipf::Config>::IpfId,
+            contract: T::AccountId,
+        },
     }
 
-    /// Errors for SmartIP pallet
+    /// Errors for IPVM pallet
     #[pallet::error]
     pub enum Error<T> {
+        /// Value and storage deposit limit add up to more than the balance type holds
         BalanceOverflow,
+        /// The contract reverted while being instantiated
+        ContractReverted,
     }
 
     /// Dispatch functions
@@ -74,107 +82,105 @@ pub mod pallet {
     where
         T::AccountId: UncheckedFrom<T::Hash>,
         T::AccountId: AsRef<[u8]>,
-
-        <T as pallet_balances::Config>::Balance: From<
-            <<T as pallet::Config>::Currency as FSCurrency<
-                <T as frame_system::Config>::AccountId,
-            >>::Balance,
-        >,
-        <<T as pallet_contracts::Config>::Currency as FSCurrency<
-            <T as frame_system::Config>::AccountId,
-        >>::Balance: From<
-            <<T as pallet::Config>::Currency as FSCurrency<
-                <T as frame_system::Config>::AccountId,
-            >>::Balance,
-        >,
     {
-        /// Create IP (Intellectual Property) Set (IPS)
-        #[pallet::weight(10000)] // TODO
+        /// Create an IP Set holding an IPF with the CID of the contract `code`, then instantiate the
+        /// contract from the IP Set account with `data` as constructor input.
+        ///
+        /// `value` is given to the contract and `storage_deposit_limit` pays the storage deposits of
+        /// the code and the contract, both are sent from `owner` to the IP Set account first.
+        #[pallet::weight(1_200_000_000u64.saturating_add(*gas_limit))]
         pub fn create(
             owner: OriginFor<T>,
+            metadata: Vec<u8>,
             code: Vec<u8>,
             data: Vec<u8>,
-            endowment: BalanceOf<T>,
+            value: BalanceOf<T>,
+            storage_deposit_limit: BalanceOf<T>,
             gas_limit: Weight,
             allow_replica: bool,
-            ipl_license: <T as ipl::Config>::Licenses,
+            ipl_license: <T as inv4::Config>::Licenses,
             ipl_execution_threshold: OneOrPercent,
             ipl_default_asset_weight: OneOrPercent,
             ipl_default_permission: bool,
-        ) -> DispatchResultWithPostInfo
-        where
-            <T as pallet_balances::Config>::Balance: From<
-                <<T as pallet::Config>::Currency as FSCurrency<
-                    <T as frame_system::Config>::AccountId,
-                >>::Balance,
-            >,
-            <<T as pallet_contracts::Config>::Currency as FSCurrency<
-                <T as frame_system::Config>::AccountId,
-            >>::Balance: From<
-                <<T as pallet::Config>::Currency as FSCurrency<
-                    <T as frame_system::Config>::AccountId,
-                >>::Balance,
-            >,
-        {
-            let ips_id: <T as ips::Config>::IpsId = ips::NextIpsId::<T>::get();
-            let ipf_id: <T as ipf::Config>::IpfId = ipf::NextIpfId::<T>::get();
-
-            ipf::Pallet::<T>::mint(owner.clone(), vec![], T::Hashing::hash(&code))?;
-
-            // TODO: WASM to WAT
-            // TODO: Mint WAT IPF
-
-            let ips_account: <T as frame_system::Config>::AccountId =
-                primitives::utils::multi_account_id::<T, <T as ips::Config>::IpsId>(ips_id, None);
-
-            ips::Pallet::<T>::create_ips(
-                owner.clone(),
-                vec![],
-                vec![ipf_id],
+        ) -> DispatchResultWithPostInfo {
+            let creator = ensure_signed(owner.clone())?;
+
+            // The IPF points to the contract code by its CIDv1, as raw binary content.
+            // IP Sets deploying the same code hold derivatives of the first one's IPF
+            let code_cid = Cid {
+                version: CidVersion::V1,
+                codec: RAW,
+                hash: SHA2_256,
+                digest: sp_io::hashing::sha2_256(&code)
+                    .to_vec()
+                    .try_into()
+                    .map_err(|_| ipf::Error::<T>::InvalidCid)?,
+            };
+
+            let ipf_id = ipf::Pallet::<T>::mint_derivable(creator.clone(), code_cid)?;
+
+            let ips_id = inv4::Pallet::<T>::inner_create_ips(
+                owner,
+                metadata,
+                vec![AnyId::IpfId(ipf_id)],
                 allow_replica,
-                None,
                 ipl_license,
                 ipl_execution_threshold,
                 ipl_default_asset_weight,
                 ipl_default_permission,
             )?;
 
-            pallet_balances::Pallet::<T>::transfer(
-                owner,
-                <<T as frame_system::Config>::Lookup as StaticLookup>::unlookup(
-                    ips_account.clone(),
-                ),
-                endowment
-                    .checked_add(&<T as pallet::Config>::ExistentialDeposit::get())
-                    .ok_or(Error::<T>::BalanceOverflow)?
-                    .into(),
+            let ips_account = derive_ips_account::<T>(ips_id, None);
+
+            <T as pallet_contracts::Config>::Currency::transfer(
+                &creator,
+                &ips_account,
+                value
+                    .checked_add(&storage_deposit_limit)
+                    .ok_or(Error::<T>::BalanceOverflow)?,
+                ExistenceRequirement::KeepAlive,
             )?;
 
-            pallet_contracts::Pallet::<T>::bare_instantiate(
+            let code_hash = pallet_contracts::Pallet::<T>::bare_upload_code(
+                ips_account.clone(),
+                code,
+                Some(storage_deposit_limit),
+            )?
+            .code_hash;
+
+            let instantiated = pallet_contracts::Pallet::<T>::bare_instantiate(
                 ips_account.clone(),
-                endowment.into(),
+                value,
                 gas_limit,
-                Some(endowment.into()),
-                pallet_contracts_primitives::Code::Existing(
-                    pallet_contracts::Pallet::<T>::bare_upload_code(
-                        ips_account.clone(),
-                        code,
-                        Some(endowment.into()),
-                    )?
-                    .code_hash,
-                ),
+                Some(storage_deposit_limit),
+                pallet_contracts_primitives::Code::Existing(code_hash),
                 data,
                 vec![],
-                true,
+                false,
             )
             .result?;
 
-            Self::deposit_event(Event::Created(ips_account, ips_id));
+            ensure!(
+                !instantiated.result.did_revert(),
+                Error::<T>::ContractReverted
+            );
+
+            Self::deposit_event(Event::Created {
+                ips_account,
+                ips_id,
+                ipf_id,
+                contract: instantiated.account_id,
+            });
 
             Ok(().into())
         }
     }
 
     #[pallet::hooks]
-    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+    impl<T: Config> Hooks<T::BlockNumber> for Pallet<T>
+    where
+        T::AccountId: UncheckedFrom<T::Hash>,
+        T::AccountId: AsRef<[u8]>,
+    {
+    }
 }
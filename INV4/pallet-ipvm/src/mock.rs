@@ -0,0 +1,249 @@
+//! Mocks for the IPVM pallet.
+
+use frame_support::{
+    construct_runtime, parameter_types,
+    traits::{ConstU128, ConstU32, ConstU64, Everything, Nothing},
+    weights::IdentityFee,
+};
+use pallet_contracts::{DefaultAddressGenerator, DefaultContractAccessWeight, Frame, Schedule};
+use sp_core::H256;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    AccountId32, Percent,
+};
+use sp_std::convert::TryInto;
+
+use super::*;
+
+use crate as ipvm;
+use inv4::{asset_handler::IpfHandler, fee::ProposerPaysFee, ipl::LicenseList};
+
+pub type AccountId = AccountId32;
+pub type Balance = u128;
+
+pub const ALICE: AccountId = AccountId32::new([1u8; 32]);
+pub const BOB: AccountId = AccountId32::new([2u8; 32]);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+    pub RuntimeBlockWeights: frame_system::limits::BlockWeights =
+        frame_system::limits::BlockWeights::simple_max(2_000_000_000_000);
+}
+
+impl frame_system::Config for Runtime {
+    type Origin = Origin;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Call = Call;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type BlockWeights = RuntimeBlockWeights;
+    type BlockLength = ();
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type DbWeight = ();
+    type BaseCallFilter = Everything;
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+parameter_types! {
+    pub const ExistentialDeposit: Balance = 1;
+}
+
+impl pallet_balances::Config for Runtime {
+    type MaxLocks = ();
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+}
+
+impl pallet_timestamp::Config for Runtime {
+    type Moment = u64;
+    type OnTimestampSet = ();
+    type MinimumPeriod = ConstU64<1>;
+    type WeightInfo = ();
+}
+
+impl pallet_randomness_collective_flip::Config for Runtime {}
+
+parameter_types! {
+    pub const MaxRoyalty: Percent = Percent::from_percent(50);
+    pub const OnDuplicateContent: ipf::DuplicateContent = ipf::DuplicateContent::Reject;
+}
+
+impl ipf::Config for Runtime {
+    type IpfId = u64;
+    type MaxIpfMetadata = ConstU32<32>;
+    type MaxIpfVersions = ConstU32<2>;
+    type OnDuplicateContent = OnDuplicateContent;
+    type Currency = Balances;
+    type MetadataDepositBase = ConstU128<1>;
+    type MetadataDepositPerByte = ConstU128<1>;
+    type MaxRoyalty = MaxRoyalty;
+    type MaxCoAuthors = ConstU32<2>;
+    type MaxIpfsPerBatch = ConstU32<3>;
+    type IpsCreator = INV4;
+    type Event = Event;
+}
+
+/// License of the IP Sets created in tests
+#[derive(Debug, Clone, Encode, Decode, TypeInfo, Eq, PartialEq)]
+pub enum Licenses {
+    Custom(BoundedVec<u8, <Runtime as inv4::Config>::MaxMetadata>, H256),
+}
+
+impl LicenseList<Runtime> for Licenses {
+    fn get_hash_and_metadata(
+        &self,
+    ) -> (
+        BoundedVec<u8, <Runtime as inv4::Config>::MaxMetadata>,
+        <Runtime as frame_system::Config>::Hash,
+    ) {
+        match self {
+            Licenses::Custom(metadata, hash) => (metadata.clone(), *hash),
+        }
+    }
+}
+
+parameter_types! {
+    pub const MaxMetadata: u32 = 32;
+}
+
+impl inv4::Config for Runtime {
+    type Event = Event;
+    type IpId = u32;
+    type Currency = Balances;
+    type Balance = Balance;
+    type ExistentialDeposit = ExistentialDeposit;
+    type Licenses = Licenses;
+    type Call = Call;
+    type WeightToFee = IdentityFee<Balance>;
+    type FeeHandler = ProposerPaysFee<Runtime>;
+    type IpfAssets = IpfHandler<Runtime>;
+    type MaxCallers = ConstU32<16>;
+    type MaxCallSize = ConstU32<1024>;
+    type PreimageBaseDeposit = ConstU128<1>;
+    type PreimageByteDeposit = ConstU128<1>;
+    type MaxSubAssets = ConstU32<16>;
+    type MaxMetadata = MaxMetadata;
+    type MaxAssetsPerIps = ConstU32<16>;
+}
+
+parameter_types! {
+    pub ContractsSchedule: Schedule<Runtime> = Default::default();
+}
+
+impl pallet_contracts::Config for Runtime {
+    type Time = Timestamp;
+    type Randomness = RandomnessCollectiveFlip;
+    type Currency = Balances;
+    type Event = Event;
+    type Call = Call;
+    type CallFilter = Nothing;
+    type WeightPrice = ();
+    type WeightInfo = ();
+    type ChainExtension = ();
+    type Schedule = ContractsSchedule;
+    type CallStack = [Frame<Self>; 31];
+    type DepositPerByte = ConstU128<1>;
+    type DepositPerItem = ConstU128<2>;
+    type AddressGenerator = DefaultAddressGenerator;
+    type ContractAccessWeight = DefaultContractAccessWeight<RuntimeBlockWeights>;
+    type MaxCodeLen = ConstU32<{ 128 * 1024 }>;
+    type RelaxedMaxCodeLen = ConstU32<{ 256 * 1024 }>;
+    type MaxStorageKeyLen = ConstU32<128>;
+}
+
+impl Config for Runtime {
+    type Event = Event;
+}
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+type Block = frame_system::mocking::MockBlock<Runtime>;
+
+construct_runtime!(
+    pub enum Runtime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Storage, Config, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+        RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Pallet, Storage},
+        Contracts: pallet_contracts::{Pallet, Call, Storage, Event<T>},
+        Ipf: ipf::{Pallet, Storage, Event<T>},
+        INV4: inv4::{Pallet, Call, Storage, Event<T>},
+        Ipvm: ipvm::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+/// A contract which does nothing at all
+pub const DUMMY_CONTRACT: &str = r#"
+(module
+    (import "env" "memory" (memory 1 1))
+    (func (export "deploy"))
+    (func (export "call"))
+)
+"#;
+
+/// A contract whose constructor reverts
+pub const REVERTING_CONTRACT: &str = r#"
+(module
+    (import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+    (import "env" "memory" (memory 1 1))
+    (func (export "deploy")
+        (call $seal_return (i32.const 1) (i32.const 0) (i32.const 0))
+    )
+    (func (export "call"))
+)
+"#;
+
+pub const MOCK_METADATA: &[u8] = b"smart contract IP Set";
+
+pub fn mock_license() -> Licenses {
+    Licenses::Custom(b"license".to_vec().try_into().unwrap(), H256::zero())
+}
+
+pub struct ExtBuilder;
+
+impl Default for ExtBuilder {
+    fn default() -> Self {
+        ExtBuilder
+    }
+}
+
+impl ExtBuilder {
+    pub fn build(self) -> sp_io::TestExternalities {
+        let mut t = frame_system::GenesisConfig::default()
+            .build_storage::<Runtime>()
+            .unwrap();
+
+        pallet_balances::GenesisConfig::<Runtime> {
+            balances: vec![(ALICE, 1_000_000), (BOB, 1_000)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
+        let mut ext = sp_io::TestExternalities::new(t);
+        ext.execute_with(|| System::set_block_number(1));
+        ext
+    }
+}
@@ -0,0 +1,138 @@
+//! Unit tests for the IPVM pallet.
+
+use super::*;
+use frame_support::{assert_noop, assert_ok};
+use mock::*;
+use primitives::{
+    cid::{RAW, SHA2_256},
+    Cid, CidVersion, OneOrPercent,
+};
+use sp_runtime::{traits::Hash, DispatchError};
+use sp_std::convert::TryInto;
+
+const GAS_LIMIT: u64 = 100_000_000_000;
+
+fn create(owner: AccountId, code: &str) -> DispatchResultWithPostInfo {
+    Ipvm::create(
+        Origin::signed(owner),
+        MOCK_METADATA.to_vec(),
+        wat::parse_str(code).unwrap(),
+        vec![],
+        100,
+        10_000,
+        GAS_LIMIT,
+        false,
+        mock_license(),
+        OneOrPercent::One,
+        OneOrPercent::One,
+        false,
+    )
+}
+
+#[test]
+fn create_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(create(ALICE, DUMMY_CONTRACT));
+
+        let code = wat::parse_str(DUMMY_CONTRACT).unwrap();
+        let ips_account = inv4::util::derive_ips_account::<Runtime>(0, None);
+        let contract = Contracts::contract_address(
+            &ips_account,
+            &<Runtime as frame_system::Config>::Hashing::hash(&code),
+            &[],
+        );
+
+        System::assert_last_event(mock::Event::Ipvm(crate::Event::Created {
+            ips_account: ips_account.clone(),
+            ips_id: 0,
+            ipf_id: 0,
+            contract,
+        }));
+
+        assert!(inv4::IpStorage::<Runtime>::contains_key(0));
+        assert!(inv4::IpsAssets::<Runtime>::contains_key(
+            0,
+            inv4::AnyId::IpfId(0)
+        ));
+
+        let ipf = ipf::IpfStorage::<Runtime>::get(0).unwrap();
+        assert_eq!(ipf.owner, ips_account);
+        assert_eq!(ipf.author, ALICE);
+        assert_eq!(
+            ipf.data,
+            Cid {
+                version: CidVersion::V1,
+                codec: RAW,
+                hash: SHA2_256,
+                digest: sp_io::hashing::sha2_256(&code).to_vec().try_into().unwrap(),
+            }
+        );
+    });
+}
+
+#[test]
+fn create_same_code_should_work() {
+    ExtBuilder::default().build().execute_with(|| {
+        // Duplicate IPFs are rejected in the mock, code IPFs are derivatives instead
+        assert_ok!(create(ALICE, DUMMY_CONTRACT));
+        assert_ok!(create(ALICE, DUMMY_CONTRACT));
+
+        assert!(inv4::IpsAssets::<Runtime>::contains_key(
+            1,
+            inv4::AnyId::IpfId(1)
+        ));
+        assert_eq!(ipf::DerivativeOf::<Runtime>::get(1), Some(0));
+
+        // No metadata deposit is taken for code IPFs
+        assert_eq!(ipf::MetadataDeposits::<Runtime>::get(0), None);
+        assert_eq!(ipf::MetadataDeposits::<Runtime>::get(1), None);
+
+        let ips_account = inv4::util::derive_ips_account::<Runtime>(1, None);
+        System::assert_last_event(mock::Event::Ipvm(crate::Event::Created {
+            ips_account: ips_account.clone(),
+            ips_id: 1,
+            ipf_id: 1,
+            contract: Contracts::contract_address(
+                &ips_account,
+                &<Runtime as frame_system::Config>::Hashing::hash(
+                    &wat::parse_str(DUMMY_CONTRACT).unwrap(),
+                ),
+                &[],
+            ),
+        }));
+    });
+}
+
+#[test]
+fn create_should_fail() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_noop!(
+            Ipvm::create(
+                Origin::none(),
+                MOCK_METADATA.to_vec(),
+                wat::parse_str(DUMMY_CONTRACT).unwrap(),
+                vec![],
+                100,
+                10_000,
+                GAS_LIMIT,
+                false,
+                mock_license(),
+                OneOrPercent::One,
+                OneOrPercent::One,
+                false,
+            ),
+            DispatchError::BadOrigin
+        );
+
+        // Can't send the value and storage deposit limit to the IP Set account
+        assert_noop!(
+            create(BOB, DUMMY_CONTRACT),
+            pallet_balances::Error::<Runtime>::InsufficientBalance
+        );
+
+        assert_noop!(
+            create(ALICE, REVERTING_CONTRACT),
+            Error::<Runtime>::ContractReverted
+        );
+    });
+}
@@ -41,6 +41,8 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
@@ -164,6 +166,7 @@ pub mod pallet {
 
     #[pallet::pallet]
     #[pallet::generate_store(pub trait Store)]
+    #[pallet::storage_version(crate::migrations::STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     /// The number of tokens staked in the system. In other words, the sum of the tokens staked to each IP set, for all IP sets.
@@ -0,0 +1,7 @@
+use frame_support::traits::StorageVersion;
+
+/// Current storage version of the pallet.
+///
+/// No storage item changed shape yet. The first migration goes in its own `vN` module here,
+/// run from `on_runtime_upgrade` along with try-runtime `pre_upgrade`/`post_upgrade` checks.
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
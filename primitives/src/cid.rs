@@ -0,0 +1,256 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::{traits::ConstU32, BoundedVec};
+use sp_std::{convert::TryInto, vec::Vec};
+
+/// Largest multihash digest a CID can carry (sha2-512, blake2b-512...)
+pub const MAX_DIGEST_LEN: u32 = 64;
+
+/// Multicodec of the dag-pb codec, the only one CIDv0 supports
+pub const DAG_PB: u64 = 0x70;
+/// Multicodec of raw binary content
+pub const RAW: u64 = 0x55;
+/// Multihash code of sha2-256, the only hash CIDv0 supports
+pub const SHA2_256: u64 = 0x12;
+
+/// Varints in CIDs are limited to 9 bytes (63 bits) by the unsigned-varint spec
+const MAX_VARINT_LEN: usize = 9;
+
+/// CID version
+#[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
+pub enum CidVersion {
+    /// Legacy CID, a bare sha2-256 multihash of a dag-pb node
+    V0,
+    /// Self describing CID with a multicodec
+    V1,
+}
+
+/// Why some bytes aren't a valid binary CID
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum CidError {
+    /// Input ended before the CID was complete
+    UnexpectedEnd,
+    /// A varint is longer than 9 bytes or not minimally encoded
+    InvalidVarint,
+    /// Only CIDv0 and CIDv1 exist
+    UnknownVersion,
+    /// Digest length doesn't match the multihash or is over `MAX_DIGEST_LEN`
+    InvalidDigestLength,
+    /// Bytes left over after the digest
+    TrailingBytes,
+}
+
+/// Content identifier of an IPFS file, stored decoded so the codec and hash don't have to be guessed
+#[derive(Encode, Decode, Clone, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
+pub struct Cid {
+    /// CID version
+    pub version: CidVersion,
+    /// Multicodec of the content, always dag-pb for CIDv0
+    pub codec: u64,
+    /// Multihash code of the hash function, always sha2-256 for CIDv0
+    pub hash: u64,
+    /// Hash digest of the content
+    pub digest: BoundedVec<u8, ConstU32<MAX_DIGEST_LEN>>,
+}
+
+impl Cid {
+    /// Parse a binary CIDv0 (34 byte sha2-256 multihash) or CIDv1
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CidError> {
+        // A CIDv0 is a sha2-256 multihash, which starts with 0x12 0x20 and can't be mistaken for
+        // a CIDv1 since version 0x12 doesn't exist
+        if bytes.len() == 34 && bytes[0] == SHA2_256 as u8 && bytes[1] == 32 {
+            return Ok(Cid {
+                version: CidVersion::V0,
+                codec: DAG_PB,
+                hash: SHA2_256,
+                digest: bytes[2..]
+                    .to_vec()
+                    .try_into()
+                    .map_err(|_| CidError::InvalidDigestLength)?,
+            });
+        }
+
+        let mut input = bytes;
+
+        let version = match read_varint(&mut input)? {
+            1 => CidVersion::V1,
+            _ => return Err(CidError::UnknownVersion),
+        };
+        let codec = read_varint(&mut input)?;
+        let hash = read_varint(&mut input)?;
+        let digest_len = read_varint(&mut input)? as usize;
+
+        if digest_len > MAX_DIGEST_LEN as usize || (hash == SHA2_256 && digest_len != 32) {
+            return Err(CidError::InvalidDigestLength);
+        }
+
+        if input.len() < digest_len {
+            return Err(CidError::UnexpectedEnd);
+        }
+
+        if input.len() > digest_len {
+            return Err(CidError::TrailingBytes);
+        }
+
+        Ok(Cid {
+            version,
+            codec,
+            hash,
+            digest: input
+                .to_vec()
+                .try_into()
+                .map_err(|_| CidError::InvalidDigestLength)?,
+        })
+    }
+
+    /// The same content addressed as a CIDv1, so CIDv0s and their CIDv1 compare equal
+    pub fn into_v1(self) -> Self {
+        Cid {
+            version: CidVersion::V1,
+            ..self
+        }
+    }
+
+    /// Binary form of the CID, what `from_bytes` parses
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        if self.version == CidVersion::V1 {
+            write_varint(&mut bytes, 1);
+            write_varint(&mut bytes, self.codec);
+        }
+
+        write_varint(&mut bytes, self.hash);
+        write_varint(&mut bytes, self.digest.len() as u64);
+        bytes.extend_from_slice(&self.digest);
+
+        bytes
+    }
+}
+
+/// Read an unsigned LEB128 varint off the front of `input`
+fn read_varint(input: &mut &[u8]) -> Result<u64, CidError> {
+    let mut value: u64 = 0;
+
+    for (i, byte) in input.iter().enumerate().take(MAX_VARINT_LEN) {
+        value |= u64::from(byte & 0x7f) << (i * 7);
+
+        if byte & 0x80 == 0 {
+            // Trailing zero bytes would make the same value encodable in more than one way
+            if *byte == 0 && i > 0 {
+                return Err(CidError::InvalidVarint);
+            }
+
+            *input = &input[i + 1..];
+            return Ok(value);
+        }
+    }
+
+    if input.len() < MAX_VARINT_LEN {
+        Err(CidError::UnexpectedEnd)
+    } else {
+        Err(CidError::InvalidVarint)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_bytes(codec: u8, hash: u8, digest: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![1, codec, hash, digest.len() as u8];
+        bytes.extend_from_slice(digest);
+        bytes
+    }
+
+    #[test]
+    fn v0_should_parse() {
+        let mut bytes = vec![0x12, 0x20];
+        bytes.extend_from_slice(&[7u8; 32]);
+
+        let cid = Cid::from_bytes(&bytes).unwrap();
+
+        assert_eq!(cid.version, CidVersion::V0);
+        assert_eq!(cid.codec, DAG_PB);
+        assert_eq!(cid.hash, SHA2_256);
+        assert_eq!(cid.digest.to_vec(), vec![7u8; 32]);
+        assert_eq!(cid.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn v1_should_parse() {
+        let bytes = v1_bytes(RAW as u8, SHA2_256 as u8, &[7u8; 32]);
+
+        let cid = Cid::from_bytes(&bytes).unwrap();
+
+        assert_eq!(cid.version, CidVersion::V1);
+        assert_eq!(cid.codec, RAW);
+        assert_eq!(cid.hash, SHA2_256);
+        assert_eq!(cid.digest.to_vec(), vec![7u8; 32]);
+        assert_eq!(cid.to_bytes(), bytes);
+
+        // Multi-byte codec varint (dag-jose, 0x85)
+        let mut bytes = vec![1, 0x85, 0x01, SHA2_256 as u8, 32];
+        bytes.extend_from_slice(&[7u8; 32]);
+        assert_eq!(Cid::from_bytes(&bytes).unwrap().codec, 0x85);
+    }
+
+    #[test]
+    fn truncated_varint_should_fail() {
+        // Codec varint has its continuation bit set but the input ends
+        assert_eq!(Cid::from_bytes(&[1, 0x80]), Err(CidError::UnexpectedEnd));
+
+        // Over 9 bytes long
+        assert_eq!(
+            Cid::from_bytes(&[1, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01]),
+            Err(CidError::InvalidVarint)
+        );
+
+        // Not minimally encoded
+        assert_eq!(
+            Cid::from_bytes(&[1, 0xd5, 0x00]),
+            Err(CidError::InvalidVarint)
+        );
+    }
+
+    #[test]
+    fn trailing_bytes_should_fail() {
+        let mut bytes = v1_bytes(RAW as u8, SHA2_256 as u8, &[7u8; 32]);
+        bytes.push(0);
+
+        assert_eq!(Cid::from_bytes(&bytes), Err(CidError::TrailingBytes));
+
+        // Digest shorter than its declared length
+        let mut bytes = v1_bytes(RAW as u8, SHA2_256 as u8, &[7u8; 32]);
+        bytes.pop();
+
+        assert_eq!(Cid::from_bytes(&bytes), Err(CidError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn long_digest_should_fail() {
+        // sha2-512 (0x13) with a 65 byte digest
+        let bytes = v1_bytes(RAW as u8, 0x13, &[7u8; 65]);
+
+        assert_eq!(Cid::from_bytes(&bytes), Err(CidError::InvalidDigestLength));
+
+        // 64 bytes is the limit
+        let bytes = v1_bytes(RAW as u8, 0x13, &[7u8; 64]);
+
+        assert_eq!(Cid::from_bytes(&bytes).unwrap().digest.len(), 64);
+
+        // sha2-256 digests are always 32 bytes
+        let bytes = v1_bytes(RAW as u8, SHA2_256 as u8, &[7u8; 31]);
+
+        assert_eq!(Cid::from_bytes(&bytes), Err(CidError::InvalidDigestLength));
+    }
+}
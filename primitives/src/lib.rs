@@ -4,6 +4,10 @@ use codec::{Decode, Encode, MaxEncodedLen};
 use scale_info::TypeInfo;
 use sp_runtime::Percent;
 
+pub mod cid;
+
+pub use cid::{Cid, CidVersion};
+
 /// Voting weight of an IPT
 #[derive(Encode, Decode, Clone, Copy, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
 pub enum OneOrPercent {
@@ -39,13 +43,11 @@ pub enum BoolOrWasm<Wasm> {
 
 /// Core IP Set struct
 #[derive(Encode, Decode, Clone, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
-pub struct IpInfo<AccountId, Data, IpsMetadataOf, IpId, Balance, LicenseMetadata, Hash> {
+pub struct IpInfo<AccountId, IpsMetadataOf, IpId, Balance, LicenseMetadata, Hash> {
     /// IPS parentage
     pub parentage: Parentage<AccountId, IpId>,
     /// IPS metadata
     pub metadata: IpsMetadataOf,
-    /// IPS children. Holds list of all items the IP Set directly owns.
-    pub data: Data,
     /// IPS Type
     pub ips_type: IpsType<IpId>,
     /// If this IPS allows replicas
@@ -75,11 +77,37 @@ pub struct IpfInfo<AccountId, Data, IpfMetadataOf> {
     pub data: Data,
 }
 
-// This is a struct in preparation for having more fields in the future.
+/// Co-author of an IPF
+#[derive(Encode, Decode, Clone, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
+pub struct CoAuthor<AccountId> {
+    /// Co-author account
+    pub account: AccountId,
+    /// Share of the IPF's royalties the co-author receives, the author keeps the rest
+    pub share: Percent,
+    /// Whether the co-author accepted the invitation, until then they receive nothing
+    pub accepted: bool,
+}
+
+/// A previous version of an IPF, recorded when it gets amended
+#[derive(Encode, Decode, Clone, Eq, PartialEq, MaxEncodedLen, Debug, TypeInfo)]
+pub struct IpfVersion<AccountId, Data, BlockNumber> {
+    /// IPF data before the amendment
+    pub data: Data,
+    /// Block the amendment happened in
+    pub amended_at: BlockNumber,
+    /// Account that amended the IPF
+    pub editor: AccountId,
+}
+
+/// Sub token Info
 #[derive(Debug, Clone, Encode, Decode, Eq, PartialEq, MaxEncodedLen, TypeInfo)]
-pub struct SubIptInfo<IptId, SubAssetMetadata> {
+pub struct SubIptInfo<IptId, SubAssetMetadata, Balance> {
+    /// Sub token ID
     pub id: IptId,
+    /// Sub token metadata
     pub metadata: SubAssetMetadata,
+    /// Total issuance of the sub token
+    pub supply: Balance,
 }
 
 #[derive(Debug, Clone, Encode, Decode, Eq, PartialEq, MaxEncodedLen, TypeInfo)]